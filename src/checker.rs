@@ -0,0 +1,510 @@
+pub mod types;
+
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::lexer;
+use crate::lexer::token::{Span, Token, TokenKind};
+use crate::parser;
+use crate::parser::tree::Assignment;
+use crate::parser::tree::Binary;
+use crate::parser::tree::Call;
+use crate::parser::tree::Constant;
+use crate::parser::tree::ExpressionNode;
+use crate::parser::tree::Get;
+use crate::parser::tree::Import;
+use crate::parser::tree::Literal;
+use crate::parser::tree::Statement;
+use crate::parser::tree::StatementNode;
+use crate::parser::tree::StatementVisitor;
+use crate::parser::tree::Try;
+use crate::parser::tree::Unary;
+use crate::parser::tree::Update;
+use crate::parser::tree::VariableDefinition;
+
+use self::types::Type;
+
+struct Variable {
+    inferred: Type,
+    declared: Option<Type>,
+}
+
+impl Variable {
+    fn current(&self) -> Type {
+        self.declared
+            .clone()
+            .unwrap_or_else(|| self.inferred.clone())
+    }
+}
+
+struct StatementChecker<'a> {
+    tree: &'a Statement,
+    source: &'a str,
+    variables: &'a mut HashMap<String, Variable>,
+    errors: &'a mut Vec<Error>,
+}
+
+impl<'a> StatementChecker<'a> {
+    fn check(&mut self, index: usize) -> Type {
+        self.tree.tree.get(index).unwrap().visit(self)
+    }
+
+    fn report(&mut self, message: String, span: Span) {
+        self.errors.push(Error::type_mismatch(message, span));
+    }
+
+    fn expect_number(&mut self, value: &Type, span: Span) {
+        if value.is_known() && *value != Type::Number {
+            self.report(format!("Cannot cast {} to number", value), span);
+        }
+    }
+
+    /// Mirrors `operators::binary`, reporting operands that are known to fail.
+    fn check_operator(&mut self, left: &Type, operator: Token, right: &Type) -> Type {
+        let span = operator.span();
+
+        match operator.kind {
+            TokenKind::Plus => match (left, right) {
+                (Type::Number, Type::Number) => Type::Number,
+                (Type::String, _) | (_, Type::String) => Type::String,
+                (Type::Any, _) | (_, Type::Any) => Type::Any,
+                _ => {
+                    self.report(
+                        "Operator + operands must be strings or numbers".to_string(),
+                        span,
+                    );
+                    Type::Any
+                }
+            },
+            TokenKind::Greater
+            | TokenKind::GreaterEqual
+            | TokenKind::Less
+            | TokenKind::LessEqual => {
+                let comparable = matches!(
+                    (left, right),
+                    (Type::Number, Type::Number)
+                        | (Type::String, Type::String)
+                        | (Type::Any, _)
+                        | (_, Type::Any)
+                );
+                if !comparable {
+                    self.report(format!("Cannot compare {} with {}", left, right), span);
+                }
+                Type::Boolean
+            }
+            TokenKind::EqualEqual | TokenKind::BangEqual => Type::Boolean,
+            _ => {
+                self.expect_number(left, span);
+                self.expect_number(right, span);
+                Type::Number
+            }
+        }
+    }
+
+    fn get_callee_name(&self, callee: usize) -> &'a str {
+        match self.tree.tree.get(callee) {
+            Some(StatementNode::Expression(ExpressionNode::Variable(token))) => {
+                self.get_token_value(token)
+            }
+            _ => "function",
+        }
+    }
+
+    fn get_token_value(&self, token: &Token) -> &'a str {
+        &self.source[token.offset..token.end]
+    }
+}
+
+impl StatementVisitor<Type> for StatementChecker<'_> {
+    fn handle_literal_expression(&mut self, literal: &Literal) -> Type {
+        self.handle_constant_expression(&literal.constant(self.source))
+    }
+
+    fn handle_binary_expression(&mut self, binary: &Binary) -> Type {
+        let left = self.check(binary.left);
+        let right = self.check(binary.right);
+
+        if binary.operator.kind == TokenKind::QuestionQuestion {
+            return match left {
+                Type::Nil => right,
+                Type::Any if right != Type::Nil => Type::Any,
+                _ => left,
+            };
+        }
+
+        self.check_operator(&left, binary.operator, &right)
+    }
+
+    fn handle_grouping_expression(&mut self, index: usize) -> Type {
+        self.check(index)
+    }
+
+    fn handle_unary_expression(&mut self, unary: &Unary) -> Type {
+        let right = self.check(unary.right);
+
+        match unary.operator.kind {
+            TokenKind::Bang => Type::Boolean,
+            _ => {
+                self.expect_number(&right, unary.operator.span());
+                Type::Number
+            }
+        }
+    }
+
+    fn handle_variable_expression(&mut self, variable: &Token) -> Type {
+        let name = self.get_token_value(variable);
+
+        match self.variables.get(name) {
+            Some(variable) => variable.current(),
+            None => {
+                self.report(format!("Undeclared variable {}", name), variable.span());
+                Type::Any
+            }
+        }
+    }
+
+    fn handle_assignment_expression(&mut self, assignment: &Assignment) -> Type {
+        let name = self.get_token_value(&assignment.target);
+        let right = self.check(assignment.value);
+
+        let Some(variable) = self.variables.get(name) else {
+            let span = assignment.target.span();
+            self.report(format!("Undeclared variable {}", name), span);
+            return right;
+        };
+        let (current, declared) = (variable.current(), variable.declared.clone());
+
+        let value = match assignment.operator.kind.compound_operator() {
+            Some(kind) => {
+                let operator = Token {
+                    kind,
+                    ..assignment.operator
+                };
+                self.check_operator(&current, operator, &right)
+            }
+            None => right,
+        };
+
+        if let Some(declared) = declared {
+            if !declared.accepts(&value) {
+                let message = format!("Cannot assign {} to {} of type {}", value, name, declared);
+                self.report(message, assignment.operator.span());
+            }
+        }
+
+        if let Some(variable) = self.variables.get_mut(name) {
+            variable.inferred = value.clone();
+        }
+
+        value
+    }
+
+    fn handle_update_expression(&mut self, update: &Update) -> Type {
+        let name = self.get_token_value(&update.target);
+
+        match self.variables.get(name).map(Variable::current) {
+            Some(current) => self.expect_number(&current, update.operator.span()),
+            None => self.report(
+                format!("Undeclared variable {}", name),
+                update.target.span(),
+            ),
+        }
+
+        Type::Number
+    }
+
+    fn handle_interpolation_expression(&mut self, parts: &[usize]) -> Type {
+        for part in parts {
+            self.check(*part);
+        }
+
+        Type::String
+    }
+
+    fn handle_call_expression(&mut self, call: &Call) -> Type {
+        let callee = self.check(call.callee);
+        if call.optional && callee == Type::Nil {
+            return Type::Nil;
+        }
+
+        let arguments = call
+            .arguments
+            .iter()
+            .map(|argument| (*argument, self.check(*argument)))
+            .collect::<Vec<_>>();
+
+        let signature = match callee {
+            Type::Function(Some(signature)) => signature,
+            Type::Function(None) | Type::Any => return Type::Any,
+            _ => {
+                let message = format!("Can only call functions, got {}", callee);
+                self.report(message, call.paren.span());
+                return Type::Any;
+            }
+        };
+
+        let name = self.get_callee_name(call.callee);
+        if arguments.len() != signature.parameters.len() {
+            let message = format!(
+                "{} expected {} arguments but got {}",
+                name,
+                signature.parameters.len(),
+                arguments.len()
+            );
+            self.report(message, call.paren.span());
+        }
+
+        for (position, ((index, argument), parameter)) in
+            arguments.iter().zip(&signature.parameters).enumerate()
+        {
+            if !parameter.accepts(argument) {
+                let message = format!(
+                    "Argument {} of {} must be {}, got {}",
+                    position + 1,
+                    name,
+                    parameter,
+                    argument
+                );
+                self.report(message, self.tree.spans[*index]);
+            }
+        }
+
+        signature.returns.clone()
+    }
+
+    fn handle_get_expression(&mut self, get: &Get) -> Type {
+        let object = self.check(get.object);
+        if get.optional && object == Type::Nil {
+            return Type::Nil;
+        }
+
+        if object.is_known() && !matches!(object, Type::Module | Type::Error) {
+            let message = format!("Only modules and errors have properties, got {}", object);
+            self.report(message, get.name.span());
+        }
+
+        Type::Any
+    }
+
+    fn handle_constant_expression(&mut self, constant: &Constant) -> Type {
+        match constant {
+            Constant::Number(_) => Type::Number,
+            Constant::String(_) => Type::String,
+            Constant::Boolean(_) => Type::Boolean,
+            Constant::Nil => Type::Nil,
+        }
+    }
+
+    fn handle_variable_definition_statement(&mut self, declaration: &VariableDefinition) -> Type {
+        let name = self.get_token_value(&declaration.identifier);
+        let value = self.check(declaration.expression);
+
+        let declared = declaration.annotation.and_then(|annotation| {
+            let type_name = self.get_token_value(&annotation);
+            let declared = Type::from_name(type_name);
+            if declared.is_none() {
+                self.report(format!("Unknown type {}", type_name), annotation.span());
+            }
+            declared
+        });
+
+        if let Some(declared) = &declared {
+            if !declared.accepts(&value) {
+                let message = format!("Cannot assign {} to {} of type {}", value, name, declared);
+                self.report(message, self.tree.spans[declaration.expression]);
+            }
+        }
+
+        let variable = Variable {
+            inferred: value,
+            declared,
+        };
+        self.variables.insert(name.to_string(), variable);
+
+        Type::Nil
+    }
+
+    fn handle_print_statement(&mut self, expression: usize) -> Type {
+        self.check(expression);
+        Type::Nil
+    }
+
+    fn handle_import_statement(&mut self, import: &Import) -> Type {
+        let alias = self.get_token_value(&import.alias);
+
+        let variable = Variable {
+            inferred: Type::Module,
+            declared: None,
+        };
+        self.variables.insert(alias.to_string(), variable);
+
+        Type::Nil
+    }
+
+    fn handle_export_statement(&mut self, declaration: usize) -> Type {
+        self.check(declaration)
+    }
+
+    fn handle_block_statement(&mut self, statements: &[usize]) -> Type {
+        for statement in statements {
+            self.check(*statement);
+        }
+
+        Type::Nil
+    }
+
+    fn handle_try_statement(&mut self, statement: &Try) -> Type {
+        self.check(statement.body);
+
+        if let Some(handler) = &statement.handler {
+            let name = self.get_token_value(&handler.variable);
+            let variable = Variable {
+                inferred: Type::Any,
+                declared: None,
+            };
+            self.variables.insert(name.to_string(), variable);
+
+            self.check(handler.body);
+        }
+
+        if let Some(finalizer) = statement.finalizer {
+            self.check(finalizer);
+        }
+
+        Type::Nil
+    }
+
+    fn handle_throw_statement(&mut self, expression: usize) -> Type {
+        self.check(expression);
+        Type::Nil
+    }
+}
+
+/// Infers the type of every expression ahead of execution and reports the
+/// operations that are certain to fail, along with values that do not match
+/// their `var x: type` annotations. Annotations are not enforced at runtime.
+#[derive(Default)]
+pub struct TypeChecker {
+    variables: HashMap<String, Variable>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes a variable that exists before the checked source runs, such as a
+    /// native function or a global defined earlier in the REPL, visible.
+    pub fn declare(&mut self, name: &str, value: Type) {
+        let variable = Variable {
+            inferred: value,
+            declared: None,
+        };
+        self.variables.insert(name.to_string(), variable);
+    }
+
+    /// Returns every type error found, syntax errors are returned as the error.
+    pub fn check(&mut self, source: &str) -> Result<Vec<Error>> {
+        let statements = parser::parse(lexer::tokenize(source)?)?;
+
+        let mut errors = Vec::new();
+        for statement in statements.iter() {
+            let mut checker = StatementChecker {
+                tree: statement,
+                source,
+                variables: &mut self.variables,
+                errors: &mut errors,
+            };
+            checker.check(statement.root_index);
+        }
+
+        Ok(errors)
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::types::Type;
+
+    #[allow(unused_imports)]
+    use crate::interpreter::ProgramContext;
+
+    #[allow(unused_imports)]
+    use crate::ErrorKind;
+
+    #[allow(dead_code)]
+    fn check(source: &str) -> Vec<String> {
+        ProgramContext::new()
+            .check(source)
+            .unwrap()
+            .into_iter()
+            .map(|error| {
+                assert_eq!(error.kind, ErrorKind::Type);
+                let span = error.span.unwrap();
+                format!("{}: {}", &source[span.offset..span.end], error.message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_accept_well_typed_programs() {
+        let source =
+            "var a: number = 1; var b = str(a) + \"!\"; a += 2; print -a ?? b; var c: any = nil;";
+        assert_eq!(check(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn should_report_annotation_mismatches() {
+        assert_eq!(
+            check("var a: number = \"1\"; var b: string = str(1); b = 2; var c: text = 1;"),
+            vec![
+                "\"1\": Cannot assign string to a of type number",
+                "=: Cannot assign number to b of type string",
+                "text: Unknown type text",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_infer_types_of_unannotated_code() {
+        assert_eq!(
+            check(concat!(
+                "var a = true; print a + 1; print -\"x\"; a = 1; print a < \"b\"; str(1, 2); x;",
+                "var n = nil; n?.y; n.z; n?.y.z(1);"
+            )),
+            vec![
+                "+: Operator + operands must be strings or numbers",
+                "-: Cannot cast string to number",
+                "<: Cannot compare number with string",
+                "(: str expected 1 arguments but got 2",
+                "x: Undeclared variable x",
+                "z: Only modules and errors have properties, got nil",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_check_registered_signatures_without_changing_execution() {
+        let mut program = ProgramContext::new();
+        program.register_typed_function("half", &[Type::Number], Type::Number, |arguments| {
+            Ok((arguments.get::<f32>(0)? / 2.0).into())
+        });
+
+        let errors = program.check("var a: string = half(\"4\");").unwrap();
+        let messages = errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "Argument 1 of half must be number, got string",
+                "Cannot assign number to a of type string"
+            ]
+        );
+
+        let value = program.interpret("var b: string = 1; b;").unwrap();
+        assert_eq!(value.number().unwrap(), 1.0);
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::interpreter::object::Object;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub parameters: Vec<Type>,
+    pub returns: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Any,
+    Nil,
+    Boolean,
+    Number,
+    String,
+    /// A function, with its signature when it is known.
+    Function(Option<Rc<Signature>>),
+    Module,
+    Error,
+}
+
+impl Type {
+    /// Resolves the name used in an annotation such as `var x: number = 1;`.
+    pub fn from_name(name: &str) -> Option<Type> {
+        let value = match name {
+            "any" => Type::Any,
+            "nil" => Type::Nil,
+            "boolean" => Type::Boolean,
+            "number" => Type::Number,
+            "string" => Type::String,
+            "function" => Type::Function(None),
+            "module" => Type::Module,
+            "error" => Type::Error,
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    pub fn of(value: &Object) -> Type {
+        match value {
+            Object::None => Type::Nil,
+            Object::Boolean(_) => Type::Boolean,
+            Object::Number(_) => Type::Number,
+            Object::String(_) => Type::String,
+            Object::NativeFunction(function) => Type::Function(Some(function.signature.clone())),
+            Object::Module(_) => Type::Module,
+            Object::Error(_) => Type::Error,
+        }
+    }
+
+    /// Whether a value of type `other` can be stored where `self` is expected.
+    /// `any` is compatible in both directions, which keeps unannotated code
+    /// free of errors whenever its types cannot be inferred.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Function(_), Type::Function(_)) => true,
+            _ => self == other,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        *self != Type::Any
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Type::Any => "any",
+            Type::Nil => "nil",
+            Type::Boolean => "boolean",
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Function(_) => "function",
+            Type::Module => "module",
+            Type::Error => "error",
+        };
+
+        write!(f, "{}", name)
+    }
+}
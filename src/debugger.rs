@@ -0,0 +1,325 @@
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::interpreter::hook::{Hook, Location};
+use crate::interpreter::object::Object;
+use crate::interpreter::ProgramContext;
+use crate::source::{LineIndex, SourceId};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Continue,
+    StepInto,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+pub struct Debugger {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    /// Source names, or the trailing part of their path, with a line.
+    breakpoints: BTreeSet<(String, usize)>,
+    mode: Mode,
+    lines: Option<(SourceId, LineIndex)>,
+}
+
+impl Debugger {
+    const HELP: &'static str = "\
+break [file:]<line>   set a breakpoint, in the current source by default
+delete [file:]<line>  remove a breakpoint
+continue              run until the next breakpoint
+step                  step into the next expression
+next                  step over the current expression
+out                   step out of the current expression
+print <expr>          evaluate an expression in the current frame
+frames                list the frame stack
+list                  show the current line
+quit                  abort execution";
+
+    pub fn new(input: impl BufRead + 'static, output: impl Write + 'static) -> Self {
+        Self {
+            input: Box::new(input),
+            output: Box::new(output),
+            breakpoints: BTreeSet::new(),
+            mode: Mode::StepInto,
+            lines: None,
+        }
+    }
+
+    fn should_stop(&self, lines: &LineIndex, location: &Location) -> bool {
+        let line = lines.line(location.span.offset);
+        let at_breakpoint = location.statement
+            && self.breakpoints.iter().any(|(file, breakpoint)| {
+                *breakpoint == line && Path::new(location.name).ends_with(file)
+            });
+
+        at_breakpoint
+            || match self.mode {
+                Mode::Continue => false,
+                Mode::StepInto => true,
+                Mode::StepOver(depth) => location.depth <= depth,
+                Mode::StepOut(depth) => location.depth < depth,
+            }
+    }
+
+    fn show_location(&mut self, lines: &LineIndex, location: &Location) -> Result<()> {
+        let line = lines.line(location.span.offset);
+        let text = lines.line_text(location.source, line).unwrap_or_default();
+
+        // Columns count characters, so carets line up under non-ASCII text
+        let (_, line_end) = lines.line_range(line).unwrap_or_default();
+        let start = lines.column(location.span.offset) - 1;
+        let end = (lines.column(location.span.end.min(line_end)) - 1).max(start + 1);
+
+        writeln!(self.output, "{:>4} | {}", line, text)?;
+        writeln!(
+            self.output,
+            "     | {}{}",
+            " ".repeat(start),
+            "^".repeat(end - start)
+        )?;
+
+        Ok(())
+    }
+
+    fn show_frames(&mut self, context: &ProgramContext) -> Result<()> {
+        for (index, frame) in context.stack_frames().iter().enumerate().rev() {
+            writeln!(self.output, "#{}", index)?;
+
+            let mut variables = frame
+                .variables
+                .iter()
+                .filter(|(_, value)| !matches!(value, Object::NativeFunction(_)))
+                .collect::<Vec<_>>();
+            variables.sort_by_key(|(name, _)| name.as_str());
+
+            for (name, value) in variables {
+                writeln!(self.output, "    {} = {}", name, value.repr())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(&mut self, context: &mut ProgramContext, expression: &str) -> Result<()> {
+        let expression = expression.trim_end_matches(';');
+        match context.inspect(&format!("{};", expression)) {
+            Ok(value) => writeln!(self.output, "{}", value.repr())?,
+            Err(error) => writeln!(self.output, "{}", error)?,
+        }
+
+        Ok(())
+    }
+
+    fn prompt(
+        &mut self,
+        context: &mut ProgramContext,
+        lines: &LineIndex,
+        location: &Location,
+    ) -> Result<()> {
+        loop {
+            write!(self.output, "(debug) ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                self.mode = Mode::Continue;
+                return Ok(());
+            }
+
+            let (command, argument) = match line.trim().split_once(' ') {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (line.trim(), ""),
+            };
+
+            match command {
+                "b" | "break" | "d" | "delete" => {
+                    let (file, line) = match argument.rsplit_once(':') {
+                        Some((file, line)) => (file, line),
+                        None => (location.name, argument),
+                    };
+
+                    match line.parse::<usize>() {
+                        Ok(line) if command.starts_with('b') => {
+                            self.breakpoints.insert((file.to_string(), line));
+                            writeln!(self.output, "Breakpoint set at {}:{}", file, line)?;
+                        }
+                        Ok(line) => {
+                            self.breakpoints.remove(&(file.to_string(), line));
+                            writeln!(self.output, "Breakpoint removed from {}:{}", file, line)?;
+                        }
+                        Err(_) => writeln!(self.output, "Expected a line number")?,
+                    }
+                }
+                "c" | "continue" => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "s" | "step" => {
+                    self.mode = Mode::StepInto;
+                    return Ok(());
+                }
+                "n" | "next" => {
+                    self.mode = Mode::StepOver(location.depth);
+                    return Ok(());
+                }
+                "o" | "out" => {
+                    self.mode = Mode::StepOut(location.depth);
+                    return Ok(());
+                }
+                "p" | "print" => self.evaluate(context, argument)?,
+                "f" | "frames" => self.show_frames(context)?,
+                "l" | "list" => self.show_location(lines, location)?,
+                "q" | "quit" => return Err(Error::aborted("Execution aborted by debugger")),
+                "h" | "help" => writeln!(self.output, "{}", Self::HELP)?,
+                "" => {}
+                _ => writeln!(self.output, "Unknown command {}, try help", command)?,
+            }
+        }
+    }
+}
+
+impl Hook for Debugger {
+    fn before_evaluate(&mut self, context: &mut ProgramContext, location: Location) -> Result<()> {
+        // Sources are only re-indexed when execution moves to a different one
+        let lines = match self.lines.take() {
+            Some((id, lines)) if id == location.span.source => lines,
+            _ => LineIndex::new(location.source),
+        };
+
+        let result = if self.should_stop(&lines, &location) {
+            self.show_location(&lines, &location)
+                .and_then(|_| self.prompt(context, &lines, &location))
+        } else {
+            Ok(())
+        };
+
+        self.lines = Some((location.span.source, lines));
+        result
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use std::io::Cursor;
+
+    #[allow(unused_imports)]
+    use super::Debugger;
+
+    #[allow(unused_imports)]
+    use crate::error::ErrorKind;
+
+    #[allow(unused_imports)]
+    use crate::interpreter::io::{SharedBuffer, Streams};
+
+    #[allow(unused_imports)]
+    use crate::interpreter::ProgramContext;
+
+    #[allow(dead_code)]
+    fn debug(source: &str, commands: &str) -> (String, String) {
+        let console = SharedBuffer::new();
+        let output = SharedBuffer::new();
+
+        let streams = Streams::new(output.clone(), std::io::sink(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+
+        let debugger = Debugger::new(Cursor::new(commands.to_string()), console.clone());
+        program.set_hook(Some(Box::new(debugger)));
+
+        let _ = program.interpret(source);
+
+        (console.contents(), output.contents())
+    }
+
+    #[test]
+    fn should_stop_at_breakpoints_and_evaluate_expressions() {
+        let source = "var a = 1;\nvar b = a + 1;\nprint b;";
+        let (console, output) = debug(
+            source,
+            "break 3\ncontinue\nprint b * 10\nframes\ncontinue\n",
+        );
+
+        assert!(console.starts_with("   1 | var a = 1;\n     | ^^^^^^^^^\n"));
+        assert!(console.contains("Breakpoint set at <input>:3\n"));
+        assert!(console.contains("   3 | print b;\n     | ^^^^^^^\n"));
+        assert!(console.contains("(debug) 20\n"));
+        assert!(console.contains("#0\n    a = 1\n    b = 2\n"));
+        assert_eq!(output, "2\n");
+    }
+
+    #[test]
+    fn should_step_into_over_and_out_of_expressions() {
+        let source = "print (1 + 2) * 3;";
+        let (console, _) = debug(source, "step\nstep\nnext\nout\ncontinue\n");
+
+        let stops = console
+            .lines()
+            .filter(|line| line.starts_with("     |"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            stops,
+            vec![
+                "     | ^^^^^^^^^^^^^^^^^",
+                "     |       ^^^^^^^^^^^",
+                "     |       ^^^^^^^",
+                "     |                 ^",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_stop_at_breakpoints_in_imported_modules() {
+        let directory = std::env::temp_dir().join(format!("lang-debug-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("lib")).unwrap();
+        std::fs::write(directory.join("lib/util.lang"), "var a = 1;\nprint a + 1;").unwrap();
+
+        let main = directory.join("main.lang");
+        let source = "import \"lib/util.lang\" as util;\nprint 3;";
+        std::fs::write(&main, source).unwrap();
+
+        let console = SharedBuffer::new();
+        let output = SharedBuffer::new();
+        let streams = Streams::new(output.clone(), std::io::sink(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+
+        let commands = "break lib/util.lang:2\ncontinue\nprint a\ncontinue\n";
+        let debugger = Debugger::new(Cursor::new(commands), console.clone());
+        program.set_hook(Some(Box::new(debugger)));
+        program.interpret_file(&main, source).unwrap();
+
+        let console = console.contents();
+        assert!(console.contains("Breakpoint set at lib/util.lang:2\n"));
+        assert!(console.contains("   2 | print a + 1;\n     | ^^^^^^^^^^^\n(debug) 1\n"));
+        assert_eq!(output.contents(), "2\n3\n");
+    }
+
+    #[test]
+    fn should_abort_execution_on_quit() {
+        let source = "print 1;\nprint 2;";
+        let (_, output) = debug(source, "quit\n");
+
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn should_abort_execution_on_quit_inside_try() {
+        let source = "try {\n    print 1;\n} catch (e) {\n    print \"caught\";\n} finally {\n    print \"finally\";\n}\nprint 2;";
+
+        let console = SharedBuffer::new();
+        let output = SharedBuffer::new();
+        let streams = Streams::new(output.clone(), std::io::sink(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+
+        let debugger = Debugger::new(Cursor::new("break 2\ncontinue\nquit\n"), console.clone());
+        program.set_hook(Some(Box::new(debugger)));
+        let error = program.interpret(source).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Aborted);
+        assert!(console.contents().contains("   2 |     print 1;\n"));
+        assert_eq!(output.contents(), "");
+    }
+}
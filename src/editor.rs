@@ -0,0 +1,640 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, stdin, stdout, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::lexer;
+use crate::lexer::token::{Identifier, TokenKind, KEYWORDS};
+
+const HISTORY_SIZE: usize = 1000;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Tab,
+    KillStart,
+    KillEnd,
+    Interrupt,
+    EndOfFile,
+    Other,
+}
+
+impl Key {
+    /// Decodes one key press, including the escape sequences terminals send
+    /// for arrows and the navigation keys.
+    pub fn read(input: &mut impl Read) -> io::Result<Option<Key>> {
+        let Some(byte) = read_byte(input)? else {
+            return Ok(None);
+        };
+
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 0x08 => Key::Backspace,
+            0x01 => Key::Home,
+            0x02 => Key::Left,
+            0x03 => Key::Interrupt,
+            0x04 => Key::EndOfFile,
+            0x05 => Key::End,
+            0x06 => Key::Right,
+            0x0b => Key::KillEnd,
+            0x0e => Key::Down,
+            0x10 => Key::Up,
+            0x15 => Key::KillStart,
+            0x1b => Self::read_escape(input)?,
+            byte if byte < 0x20 => Key::Other,
+            byte => Self::read_char(input, byte)?,
+        };
+
+        Ok(Some(key))
+    }
+
+    fn read_escape(input: &mut impl Read) -> io::Result<Key> {
+        if !matches!(read_byte(input)?, Some(b'[' | b'O')) {
+            return Ok(Key::Other);
+        }
+
+        let key = match read_byte(input)? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            Some(digit @ b'0'..=b'9') => {
+                let mut code = vec![digit];
+                while let Some(byte) = read_byte(input)? {
+                    if byte == b'~' {
+                        break;
+                    }
+                    code.push(byte);
+                }
+
+                match &code[..] {
+                    b"1" | b"7" => Key::Home,
+                    b"3" => Key::Delete,
+                    b"4" | b"8" => Key::End,
+                    _ => Key::Other,
+                }
+            }
+            _ => Key::Other,
+        };
+
+        Ok(key)
+    }
+
+    fn read_char(input: &mut impl Read, first: u8) -> io::Result<Key> {
+        let length = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+
+        let mut bytes = vec![first];
+        for _ in 1..length {
+            match read_byte(input)? {
+                Some(byte) => bytes.push(byte),
+                None => break,
+            }
+        }
+
+        Ok(std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|text| text.chars().next())
+            .map_or(Key::Other, Key::Char))
+    }
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match input.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Editing,
+    Completions(Vec<String>),
+    Accept(String),
+    Interrupt,
+    EndOfFile,
+}
+
+/// The line being edited, its cursor and the position while walking history.
+#[derive(Default)]
+pub struct LineState {
+    chars: Vec<char>,
+    cursor: usize,
+    history_index: Option<usize>,
+    draft: Vec<char>,
+}
+
+impl LineState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn apply(&mut self, key: Key, history: &[String], words: &[String]) -> Outcome {
+        match key {
+            Key::Char(character) => {
+                self.chars.insert(self.cursor, character);
+                self.cursor += 1;
+            }
+            Key::Enter => return Outcome::Accept(self.text()),
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            Key::Delete if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.chars.len(),
+            Key::Up => self.walk_history(history, -1),
+            Key::Down => self.walk_history(history, 1),
+            Key::Tab => return self.complete(words),
+            Key::KillStart => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::KillEnd => self.chars.truncate(self.cursor),
+            Key::Interrupt => return Outcome::Interrupt,
+            Key::EndOfFile if self.chars.is_empty() => return Outcome::EndOfFile,
+            Key::EndOfFile if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            _ => {}
+        }
+
+        Outcome::Editing
+    }
+
+    fn walk_history(&mut self, history: &[String], step: isize) {
+        let index = match (self.history_index, step) {
+            (None, -1) if !history.is_empty() => history.len() - 1,
+            (None, _) => return,
+            (Some(0), -1) => 0,
+            (Some(index), -1) => index - 1,
+            (Some(index), _) if index + 1 < history.len() => index + 1,
+            (Some(_), _) => {
+                self.history_index = None;
+                self.chars = std::mem::take(&mut self.draft);
+                self.cursor = self.chars.len();
+                return;
+            }
+        };
+
+        if self.history_index.is_none() {
+            self.draft = std::mem::take(&mut self.chars);
+        }
+
+        self.history_index = Some(index);
+        self.chars = history[index].chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    /// Completes the word before the cursor as far as all candidates agree,
+    /// and returns the candidates when more than one remains.
+    fn complete(&mut self, words: &[String]) -> Outcome {
+        let start = self.chars[..self.cursor]
+            .iter()
+            .rposition(|ch| !ch.is_identifier_continue())
+            .map_or(0, |index| index + 1);
+        let prefix = self.chars[start..self.cursor].iter().collect::<String>();
+
+        let mut candidates = KEYWORDS
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .chain(words.iter().cloned())
+            .filter(|word| word.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+
+        let Some(first) = candidates.first() else {
+            return Outcome::Editing;
+        };
+
+        let common = candidates.iter().fold(first.len(), |length, word| {
+            first
+                .chars()
+                .zip(word.chars())
+                .take_while(|(left, right)| left == right)
+                .count()
+                .min(length)
+        });
+
+        for character in first.chars().take(common).skip(prefix.chars().count()) {
+            self.chars.insert(self.cursor, character);
+            self.cursor += 1;
+        }
+
+        match candidates.len() {
+            1 => Outcome::Editing,
+            _ => Outcome::Completions(candidates),
+        }
+    }
+}
+
+/// Colors a line of source with ANSI escapes by token kind, leaving it
+/// unchanged while it does not tokenize, e.g. inside an unterminated string.
+pub fn highlight(source: &str) -> String {
+    let Ok(tokens) = lexer::tokenize_with_trivia(source) else {
+        return source.to_string();
+    };
+
+    let mut output = String::new();
+    let mut end = 0;
+
+    for token in tokens {
+        output.push_str(&source[end..token.offset]);
+        end = token.end;
+
+        let color = match token.kind {
+            TokenKind::Number => "33",
+            TokenKind::String
+            | TokenKind::InterpolationStart
+            | TokenKind::InterpolationMiddle
+            | TokenKind::InterpolationEnd => "32",
+            TokenKind::Comment => "90",
+            TokenKind::True | TokenKind::False | TokenKind::Nil => "36",
+            TokenKind::And
+            | TokenKind::Class
+            | TokenKind::Else
+            | TokenKind::Fn
+            | TokenKind::For
+            | TokenKind::If
+            | TokenKind::Or
+            | TokenKind::Print
+            | TokenKind::Return
+            | TokenKind::Var
+            | TokenKind::While
+            | TokenKind::Import
+            | TokenKind::Export
+            | TokenKind::As
+            | TokenKind::Try
+            | TokenKind::Catch
+            | TokenKind::Finally
+            | TokenKind::Throw => "35",
+            _ => {
+                output.push_str(&source[token.offset..token.end]);
+                continue;
+            }
+        };
+
+        output.push_str(&format!(
+            "\x1b[{}m{}\x1b[0m",
+            color,
+            &source[token.offset..token.end]
+        ));
+    }
+
+    output.push_str(&source[end..]);
+    output
+}
+
+/// Switches the terminal out of line buffering for as long as it is alive.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1", "time", "0"])?;
+
+        Ok(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(arguments: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(arguments)
+        .stdin(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed"));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads lines from the terminal with editing, highlighting, completion and a
+/// history that is kept in a file between sessions.
+pub struct Editor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+    /// Lines are appended to the file as they are entered, and the file is
+    /// rewritten with only the kept history once it holds twice as many.
+    history_file_lines: usize,
+}
+
+impl Editor {
+    pub fn new(history_path: Option<PathBuf>) -> Self {
+        let mut history = history_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| contents.lines().map(String::from).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let history_file_lines = history.len();
+        let excess = history.len().saturating_sub(HISTORY_SIZE);
+        history.drain(..excess);
+
+        let mut editor = Self {
+            history,
+            history_path,
+            history_file_lines,
+        };
+        if excess > 0 {
+            editor.save_history();
+        }
+
+        editor
+    }
+
+    /// Returns `None` once input ends, `words` are offered for completion in
+    /// addition to the keywords.
+    pub fn read_line(&mut self, prompt: &str, words: &[String]) -> io::Result<Option<String>> {
+        let line = if stdin().is_terminal() {
+            match RawMode::enable() {
+                Ok(_raw) => self.edit(prompt, words)?,
+                Err(_) => Self::read_plain(prompt)?,
+            }
+        } else {
+            Self::read_plain(prompt)?
+        };
+
+        if let Some(line) = &line {
+            self.add_history(line);
+        }
+
+        Ok(line)
+    }
+
+    fn read_plain(prompt: &str) -> io::Result<Option<String>> {
+        print!("{}", prompt);
+        stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    fn edit(&mut self, prompt: &str, words: &[String]) -> io::Result<Option<String>> {
+        let mut input = stdin().lock();
+        let mut output = stdout().lock();
+        let mut state = LineState::new();
+
+        Self::render(&mut output, prompt, &state)?;
+
+        while let Some(key) = Key::read(&mut input)? {
+            match state.apply(key, &self.history, words) {
+                Outcome::Editing => {}
+                Outcome::Completions(candidates) => {
+                    write!(output, "\r\n{}\r\n", candidates.join("  "))?;
+                }
+                Outcome::Accept(line) => {
+                    write!(output, "\r\n")?;
+                    return Ok(Some(line));
+                }
+                Outcome::Interrupt => {
+                    write!(output, "^C\r\n")?;
+                    state = LineState::new();
+                }
+                Outcome::EndOfFile => {
+                    write!(output, "\r\n")?;
+                    return Ok(None);
+                }
+            }
+
+            Self::render(&mut output, prompt, &state)?;
+        }
+
+        Ok(None)
+    }
+
+    fn render(output: &mut impl Write, prompt: &str, state: &LineState) -> io::Result<()> {
+        let column = prompt.chars().count() + state.cursor();
+        write!(output, "\r{}{}\x1b[K\r", prompt, highlight(&state.text()))?;
+
+        if column > 0 {
+            write!(output, "\x1b[{}C", column)?;
+        }
+
+        output.flush()
+    }
+
+    fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+
+        self.history.push(line.to_string());
+        if self.history.len() > HISTORY_SIZE {
+            self.history.remove(0);
+        }
+
+        if self.history_file_lines >= 2 * HISTORY_SIZE {
+            self.save_history();
+            return;
+        }
+
+        if let Some(path) = &self.history_path {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            if let Ok(mut file) = file {
+                let _ = writeln!(file, "{}", line);
+                self.history_file_lines += 1;
+            }
+        }
+    }
+
+    fn save_history(&mut self) {
+        if let Some(path) = &self.history_path {
+            let contents = self
+                .history
+                .iter()
+                .map(|line| format!("{}\n", line))
+                .collect::<String>();
+
+            if fs::write(path, contents).is_ok() {
+                self.history_file_lines = self.history.len();
+            }
+        }
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::{highlight, Editor, Key, LineState, Outcome, HISTORY_SIZE};
+
+    #[allow(dead_code)]
+    fn keys(bytes: &[u8]) -> Vec<Key> {
+        let mut input = bytes;
+        let mut keys = Vec::new();
+        while let Some(key) = Key::read(&mut input).unwrap() {
+            keys.push(key);
+        }
+
+        keys
+    }
+
+    #[allow(dead_code)]
+    fn type_keys(state: &mut LineState, keys: &[Key], history: &[String]) -> Outcome {
+        let words = vec!["value".to_string(), "variance".to_string()];
+        keys.iter()
+            .map(|key| state.apply(*key, history, &words))
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn should_decode_keys_and_escape_sequences() {
+        assert_eq!(
+            keys("a\x1b[A\x1b[D\x1b[3~\x1bOH\x7f\té\r".as_bytes()),
+            vec![
+                Key::Char('a'),
+                Key::Up,
+                Key::Left,
+                Key::Delete,
+                Key::Home,
+                Key::Backspace,
+                Key::Tab,
+                Key::Char('é'),
+                Key::Enter,
+            ]
+        );
+    }
+
+    #[test]
+    fn should_edit_the_line_at_the_cursor() {
+        let mut state = LineState::new();
+        let typed = "print 12;"
+            .chars()
+            .map(Key::Char)
+            .chain([Key::Left, Key::Backspace, Key::Char('3'), Key::Home])
+            .chain([Key::Delete, Key::Char('P'), Key::End, Key::Enter])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            type_keys(&mut state, &typed, &[]),
+            Outcome::Accept("Print 13;".to_string())
+        );
+    }
+
+    #[test]
+    fn should_walk_history_and_restore_draft() {
+        let history = vec!["first;".to_string(), "second;".to_string()];
+        let mut state = LineState::new();
+
+        type_keys(&mut state, &[Key::Char('x'), Key::Up, Key::Up], &history);
+        assert_eq!(state.text(), "first;");
+
+        type_keys(&mut state, &[Key::Down], &history);
+        assert_eq!(state.text(), "second;");
+
+        type_keys(&mut state, &[Key::Down], &history);
+        assert_eq!(state.text(), "x");
+    }
+
+    #[test]
+    fn should_complete_keywords_and_variables() {
+        let mut state = LineState::new();
+        let outcome = type_keys(&mut state, &[Key::Char('p'), Key::Char('r'), Key::Tab], &[]);
+        assert_eq!(outcome, Outcome::Editing);
+        assert_eq!(state.text(), "print");
+
+        let mut state = LineState::new();
+        let outcome = type_keys(&mut state, &[Key::Char('v'), Key::Tab], &[]);
+        assert_eq!(
+            outcome,
+            Outcome::Completions(vec![
+                "value".to_string(),
+                "var".to_string(),
+                "variance".to_string()
+            ])
+        );
+        assert_eq!(state.text(), "va");
+    }
+
+    #[test]
+    fn should_highlight_tokens_by_kind() {
+        assert_eq!(
+            highlight("var a = 1; # note"),
+            "\x1b[35mvar\x1b[0m a = \x1b[33m1\x1b[0m; \x1b[90m# note\x1b[0m"
+        );
+        assert_eq!(highlight("print \"open"), "print \"open");
+    }
+
+    #[test]
+    fn should_highlight_non_ascii_text() {
+        assert_eq!(
+            highlight("print \"éé\"; # ü"),
+            "\x1b[35mprint\x1b[0m \x1b[32m\"éé\"\x1b[0m; \x1b[90m# ü\x1b[0m"
+        );
+        assert_eq!(highlight("\"éé"), "\"éé");
+    }
+
+    #[test]
+    fn should_cap_history_in_memory_and_on_disk() {
+        let path = std::env::temp_dir().join(format!("lang-history-{}", std::process::id()));
+        let lines = (0..HISTORY_SIZE * 3)
+            .map(|n| format!("{};\n", n))
+            .collect::<String>();
+        std::fs::write(&path, lines).unwrap();
+
+        let line_count = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let mut editor = Editor::new(Some(path.clone()));
+        assert_eq!(editor.history.len(), HISTORY_SIZE);
+        assert_eq!(editor.history[0], format!("{};", HISTORY_SIZE * 2));
+        assert_eq!(line_count(), HISTORY_SIZE);
+
+        for n in 0..HISTORY_SIZE * 2 {
+            editor.add_history(&format!("new {};", n));
+            assert!(line_count() <= HISTORY_SIZE * 2);
+        }
+        assert_eq!(editor.history.len(), HISTORY_SIZE);
+
+        let editor = Editor::new(Some(path.clone()));
+        assert_eq!(
+            editor.history.last().unwrap(),
+            &format!("new {};", HISTORY_SIZE * 2 - 1)
+        );
+    }
+}
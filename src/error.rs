@@ -0,0 +1,151 @@
+use std::fmt::{Display, Write};
+
+use crate::lexer::token::Span;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorKind {
+    Syntax,
+    Runtime,
+    Limit,
+    Type,
+    Thrown,
+    Aborted,
+}
+
+/// Where a frame of the call stack was executing when an error left it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceLocation {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TraceFrame {
+    pub name: String,
+    /// Absent for native functions and errors that carry no span.
+    pub location: Option<SourceLocation>,
+    pub native: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Option<Span>,
+    /// The frames the error unwound through, innermost first.
+    pub trace: Vec<TraceFrame>,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn syntax(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            kind: ErrorKind::Syntax,
+            message: message.into(),
+            span: Some(span),
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn runtime(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::Runtime,
+            message: message.into(),
+            span: None,
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn limit(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::Limit,
+            message: message.into(),
+            span: None,
+            trace: Vec::new(),
+        }
+    }
+
+    /// Stops the program on behalf of its host, such as the debugger's `quit`.
+    pub fn aborted(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::Aborted,
+            message: message.into(),
+            span: None,
+            trace: Vec::new(),
+        }
+    }
+
+    /// An error raised by a `throw` statement that no `catch` handled.
+    pub fn thrown(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            kind: ErrorKind::Thrown,
+            message: message.into(),
+            span: Some(span),
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn type_mismatch(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            kind: ErrorKind::Type,
+            message: message.into(),
+            span: Some(span),
+            trace: Vec::new(),
+        }
+    }
+
+    pub fn or_span(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    pub fn with_frame(mut self, frame: TraceFrame) -> Self {
+        self.trace.push(frame);
+        self
+    }
+
+    /// Renders the frames the error unwound through, innermost last.
+    pub fn traceback(&self) -> String {
+        let mut output = String::from("Traceback (most recent call last):\n");
+
+        for frame in self.trace.iter().rev() {
+            let _ = match &frame.location {
+                Some(location) => writeln!(
+                    output,
+                    "  at {} ({}:{}:{})\n    {}",
+                    frame.name, location.path, location.line, location.column, location.text
+                ),
+                None if frame.native => writeln!(output, "  at {} (native)", frame.name),
+                None => writeln!(output, "  at {} (unknown location)", frame.name),
+            };
+        }
+
+        output
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            ErrorKind::Syntax => "Syntax error",
+            ErrorKind::Runtime => "Runtime error",
+            ErrorKind::Limit => "Limit error",
+            ErrorKind::Type => "Type error",
+            ErrorKind::Thrown => "Uncaught exception",
+            ErrorKind::Aborted => "Aborted",
+        };
+
+        write!(f, "{}: {}", kind, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::runtime(format!("I/O failure: {}", error))
+    }
+}
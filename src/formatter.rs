@@ -0,0 +1,271 @@
+use crate::error::Result;
+use crate::lexer::token::{Token, TokenKind};
+use crate::{lexer, parser};
+
+struct Formatter<'a> {
+    source: &'a str,
+    output: String,
+    newlines: usize,
+    previous: TokenKind,
+    operand: bool,
+    prefix: bool,
+    parens: usize,
+    depth: usize,
+    break_line: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            output: String::new(),
+            newlines: 0,
+            previous: TokenKind::None,
+            operand: false,
+            prefix: false,
+            parens: 0,
+            depth: 0,
+            break_line: false,
+        }
+    }
+
+    fn format(mut self, tokens: &[Token]) -> String {
+        for token in tokens {
+            match token.kind {
+                TokenKind::Whitespace => {
+                    self.newlines += self.get_token_value(token).matches('\n').count();
+                }
+                TokenKind::Comment => self.add_comment(token),
+                _ => self.add_token(token),
+            }
+        }
+
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output
+    }
+
+    fn add_comment(&mut self, token: &Token) {
+        let comment = self.get_token_value(token).trim_end();
+
+        if self.output.is_empty() || self.newlines > 0 {
+            self.start_line();
+        } else {
+            self.output.push(' ');
+        }
+
+        self.output.push_str(comment);
+
+        self.newlines = 0;
+        self.break_line = true;
+    }
+
+    fn add_token(&mut self, token: &Token) {
+        let prefix = self.is_prefix_operator(token.kind);
+
+        // `} catch` and `} finally` stay on the line that closes the block.
+        let continues = self.previous == TokenKind::RightBrace
+            && matches!(token.kind, TokenKind::Catch | TokenKind::Finally);
+
+        if token.kind == TokenKind::RightBrace {
+            self.depth = self.depth.saturating_sub(1);
+        }
+
+        if self.output.is_empty()
+            || token.kind == TokenKind::RightBrace
+            || (self.break_line && !continues)
+        {
+            self.start_line();
+        } else if continues || self.needs_space(token) {
+            self.output.push(' ');
+        }
+
+        self.output.push_str(self.get_token_value(token));
+        self.newlines = 0;
+        self.break_line = (token.kind == TokenKind::Semicolon && self.parens == 0)
+            || matches!(token.kind, TokenKind::LeftBrace | TokenKind::RightBrace);
+
+        match token.kind {
+            TokenKind::LeftParen => self.parens += 1,
+            TokenKind::RightParen => self.parens = self.parens.saturating_sub(1),
+            TokenKind::LeftBrace => self.depth += 1,
+            _ => {}
+        }
+
+        self.operand = match token.kind {
+            TokenKind::Identifier
+            | TokenKind::Number
+            | TokenKind::String
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Nil
+            | TokenKind::RightParen
+            | TokenKind::InterpolationEnd => true,
+            TokenKind::PlusPlus | TokenKind::MinusMinus => !prefix,
+            _ => false,
+        };
+        self.prefix = prefix;
+        self.previous = token.kind;
+    }
+
+    /// Keeps a single blank line where the source had one or more, and
+    /// indents by four spaces per enclosing block.
+    fn start_line(&mut self) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        if self.newlines > 1 && !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output.push_str(&"    ".repeat(self.depth));
+        self.break_line = false;
+    }
+
+    fn is_prefix_operator(&self, kind: TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::Minus
+                | TokenKind::Bang
+                | TokenKind::Tilde
+                | TokenKind::PlusPlus
+                | TokenKind::MinusMinus
+        ) && !self.operand
+    }
+
+    fn needs_space(&self, token: &Token) -> bool {
+        let kind = token.kind;
+        let postfix = matches!(kind, TokenKind::PlusPlus | TokenKind::MinusMinus) && self.operand;
+        let call = kind == TokenKind::LeftParen && self.operand;
+        let property = matches!(kind, TokenKind::Dot | TokenKind::QuestionDot)
+            || matches!(self.previous, TokenKind::Dot | TokenKind::QuestionDot);
+
+        let closing = matches!(
+            kind,
+            TokenKind::Semicolon
+                | TokenKind::Comma
+                | TokenKind::Colon
+                | TokenKind::RightParen
+                | TokenKind::InterpolationMiddle
+                | TokenKind::InterpolationEnd
+        );
+        let opening = matches!(
+            self.previous,
+            TokenKind::LeftParen | TokenKind::InterpolationStart | TokenKind::InterpolationMiddle
+        );
+
+        // `- -a` must not become `--a`, which lexes as a decrement
+        let value = self.get_token_value(token);
+        let glued = ['-', '+']
+            .iter()
+            .any(|sign| self.output.ends_with(*sign) && value.starts_with(*sign));
+
+        glued || !(postfix || call || property || closing || opening || self.prefix)
+    }
+
+    fn get_token_value(&self, token: &Token) -> &'a str {
+        &self.source[token.offset..token.end]
+    }
+}
+
+/// Re-emits a program in canonical style, one statement per line with single
+/// spaces around binary operators, keeping comments and single blank lines.
+/// Programs that do not parse are returned as an error instead.
+pub fn format(source: &str) -> Result<String> {
+    parser::parse(lexer::tokenize(source)?)?;
+
+    let tokens = lexer::tokenize_with_trivia(source)?;
+    Ok(Formatter::new(source).format(&tokens))
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::format;
+
+    #[allow(unused_imports)]
+    use crate::lexer::tokenize;
+
+    #[allow(unused_imports)]
+    use crate::parser::{parse, printer::DebugPrint};
+
+    #[allow(dead_code)]
+    fn syntax_tree(source: &str) -> Vec<String> {
+        parse(tokenize(source).unwrap())
+            .unwrap()
+            .iter()
+            .map(|statement| statement.debug_print(source))
+            .collect()
+    }
+
+    #[test]
+    fn should_format_spacing_and_line_breaks() {
+        let source = "var  a=1;var b : number = -a*(a+2 )**2;print str( a , b );a++;--b;";
+
+        assert_eq!(
+            format(source).unwrap(),
+            "var a = 1;\nvar b: number = -a * (a + 2) ** 2;\nprint str(a, b);\na++;\n--b;\n"
+        );
+        assert_eq!(syntax_tree(&format(source).unwrap()), syntax_tree(source));
+    }
+
+    #[test]
+    fn should_keep_comments_and_single_blank_lines() {
+        let source = "# header\nvar a = 1;   # trailing\n\n\n\nprint\n  a;\n# footer";
+
+        assert_eq!(
+            format(source).unwrap(),
+            "# header\nvar a = 1; # trailing\n\nprint a;\n# footer\n"
+        );
+    }
+
+    #[test]
+    fn should_format_interpolations_and_keep_formatted_source() {
+        let source = "print \"a ${ b+1 } c ${d}\" ?? !e ?. f;\n";
+        let formatted = format(source).unwrap();
+
+        assert_eq!(formatted, "print \"a ${b + 1} c ${d}\" ?? !e?.f;\n");
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn should_indent_blocks() {
+        let source = "try{ throw 1;}catch(e){print e;} finally {\n\n{ a; }}";
+
+        assert_eq!(
+            format(source).unwrap(),
+            "try {\n    throw 1;\n} catch (e) {\n    print e;\n} finally {\n\n    {\n        a;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn should_format_non_ascii_comments_and_strings() {
+        let source = "# café\nvar  abc=\"né\";print abc;";
+
+        assert_eq!(
+            format(source).unwrap(),
+            "# café\nvar abc = \"né\";\nprint abc;\n"
+        );
+    }
+
+    #[test]
+    fn should_keep_the_syntax_tree_of_prefix_operators() {
+        let source =
+            "print - -a;print - - -a;print -(--a);print - --a;print !-a;print ~ - - ~a;a = b - -c;";
+        let formatted = format(source).unwrap();
+
+        assert_eq!(
+            formatted,
+            "print - -a;\nprint - - -a;\nprint -(--a);\nprint - --a;\nprint !-a;\nprint ~- -~a;\na = b - -c;\n"
+        );
+        assert_eq!(syntax_tree(&formatted), syntax_tree(source));
+    }
+
+    #[test]
+    fn should_reject_invalid_programs() {
+        assert!(format("print 1 +;").is_err());
+    }
+}
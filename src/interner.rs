@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Mutex, OnceLock};
+
+/// A compact handle for an identifier. Interning the same name always yields
+/// the same symbol, so symbols compare and hash as cheaply as integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+/// The table is shared by the whole process and names are never freed, so it
+/// grows with every distinct identifier interned. Only source that is run gets
+/// interned; tools that lex on every keystroke, such as the editor and the
+/// language server, leave identifiers as plain text.
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Mutex::default)
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        let mut interner = interner().lock().unwrap();
+        if let Some(symbol) = interner.symbols.get(name) {
+            return *symbol;
+        }
+
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        let symbol = Symbol(interner.names.len() as u32);
+        interner.names.push(name);
+        interner.symbols.insert(name, symbol);
+
+        symbol
+    }
+
+    /// Finds the symbol of a name that was interned before, without adding it.
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        interner().lock().unwrap().symbols.get(name).copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        interner().lock().unwrap().names[self.0 as usize]
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::Symbol;
+
+    #[test]
+    fn should_intern_equal_names_once() {
+        let first = Symbol::intern("interned_name");
+        let second = Symbol::intern(&String::from("interned_name"));
+
+        assert_eq!(first, second);
+        assert_ne!(first, Symbol::intern("other_name"));
+        assert_eq!(first.as_str(), "interned_name");
+        assert_eq!(second.to_string(), "interned_name");
+
+        assert_eq!(Symbol::lookup("interned_name"), Some(first));
+        assert_eq!(Symbol::lookup("never_interned_name"), None);
+        assert_eq!(Symbol::lookup("never_interned_name"), None);
+    }
+}
@@ -1,176 +1,467 @@
-use std::collections::HashMap;
+pub mod exception;
+pub mod hook;
+pub mod io;
+pub mod limits;
+pub mod module;
+pub mod native;
+pub mod object;
+pub mod operators;
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::checker::types::Type;
+use crate::checker::TypeChecker;
+use crate::error::{Error, ErrorKind, Result, TraceFrame};
+use crate::interner::Symbol;
 use crate::lexer;
 use crate::lexer::token::Token;
 use crate::lexer::token::TokenKind;
+use crate::optimizer;
+use crate::source::{SourceId, SourceRegistry};
 
 use crate::parser;
+use crate::parser::tree::Assignment;
 use crate::parser::tree::Binary;
+use crate::parser::tree::Call;
+use crate::parser::tree::Constant;
+use crate::parser::tree::ExpressionNode;
+use crate::parser::tree::Get;
+use crate::parser::tree::Import;
 use crate::parser::tree::Literal;
 use crate::parser::tree::Statement;
+use crate::parser::tree::StatementNode;
 use crate::parser::tree::StatementVisitor;
+use crate::parser::tree::Try;
 use crate::parser::tree::Unary;
+use crate::parser::tree::Update;
 use crate::parser::tree::VariableDefinition;
 
-#[derive(Debug, Clone)]
-pub enum Object {
-    String(String),
-    Boolean(bool),
-    Number(f32),
-    None,
-}
-
-impl Object {
-    fn number(&self) -> f32 {
-        match self {
-            Object::Number(value) => *value,
-            _ => panic!("Canot cast {:?} to boolean", self),
-        }
-    }
-
-    fn boolean(&self) -> bool {
-        match self {
-            Object::Boolean(value) => *value,
-            _ => true,
-        }
-    }
+use self::exception::Exception;
+use self::hook::{Hook, Location};
+use self::io::Streams;
+use self::limits::{Budget, Limits};
+use self::module::{Module, ModuleLoader};
+use self::native::{Arguments, NativeFunction};
+use self::object::Object;
 
-    fn equals(&self, rhs: &Object) -> bool {
-        match (self, rhs) {
-            (Object::Number(lhs), Object::Number(rhs)) => lhs == rhs,
-            (Object::String(lhs), Object::String(rhs)) => lhs == rhs,
-            (Object::Boolean(lhs), Object::Boolean(rhs)) => lhs == rhs,
-            _ => panic!("Cannot compare {:?} to {:?}", self, rhs),
-        }
-    }
-}
+type HookEvent = fn(&mut (dyn Hook + 'static), &mut ProgramContext, Location) -> Result<()>;
 
 struct StatementInterpreter<'a> {
     context: &'a mut ProgramContext,
     tree: &'a Statement,
     source: &'a str,
+    name: &'a str,
+    /// The nodes that are whole statements, collected only for hooks.
+    statements: HashSet<usize>,
 }
 
 impl<'a> StatementInterpreter<'a> {
     pub fn new(
         tree: &'a Statement,
         source: &'a str,
+        name: &'a str,
         context: &'a mut ProgramContext,
     ) -> StatementInterpreter<'a> {
+        let mut statements = HashSet::new();
+        if context.hook.is_some() {
+            statements.insert(tree.root_index);
+            for node in tree.tree.iter() {
+                if let StatementNode::Block(children) = node {
+                    statements.extend(children);
+                }
+            }
+        }
+
         StatementInterpreter {
             tree,
             source,
+            name,
+            statements,
             context,
         }
     }
 
-    pub fn interpret(&mut self) -> Object {
+    pub fn interpret(&mut self) -> Result<Object> {
         self.evaluate(self.tree.root_index)
     }
 
-    fn evaluate(&mut self, index: usize) -> Object {
-        self.tree.tree.get(index).unwrap().visit(self)
+    fn evaluate(&mut self, index: usize) -> Result<Object> {
+        let result = self
+            .context
+            .budget
+            .enter()
+            .and_then(|_| self.notify(index, <dyn Hook>::before_evaluate))
+            .and_then(|_| self.tree.tree.get(index).unwrap().visit(self));
+
+        let notified = self.notify(index, <dyn Hook>::after_evaluate);
+        let result = result.and_then(|value| notified.map(|_| value));
+
+        self.context.budget.exit();
+
+        let value = result?;
+        self.context.budget.check(&value)?;
+
+        Ok(value)
+    }
+
+    fn notify(&mut self, index: usize, event: HookEvent) -> Result<()> {
+        let Some(mut hook) = self.context.hook.take() else {
+            return Ok(());
+        };
+
+        let location = Location {
+            source: self.source,
+            name: self.name,
+            span: self.tree.spans[index],
+            statement: self.statements.contains(&index),
+            depth: self.context.budget.depth(),
+            function: self.get_function_name(index),
+        };
+
+        let result = event(hook.as_mut(), self.context, location);
+        self.context.hook = Some(hook);
+
+        result
+    }
+
+    fn get_function_name(&self, index: usize) -> Option<&'a str> {
+        let Some(StatementNode::Expression(ExpressionNode::Call(call))) = self.tree.tree.get(index)
+        else {
+            return None;
+        };
+
+        match self.tree.tree.get(call.callee) {
+            Some(StatementNode::Expression(ExpressionNode::Variable(token))) => {
+                Some(self.get_token_value(token))
+            }
+            _ => Some("<anonymous>"),
+        }
+    }
+
+    /// Takes the value passed to `throw` when the result is the error it raised.
+    fn take_thrown(&mut self, result: &Result<Object>) -> Option<Object> {
+        match result {
+            Err(error) if error.kind == ErrorKind::Thrown => self.context.thrown.take(),
+            _ => None,
+        }
     }
 
     fn get_token_value(&self, token: &Token) -> &'a str {
         &self.source[token.offset..token.end]
     }
 
+    fn get_symbol(&self, token: &Token) -> Symbol {
+        token
+            .symbol
+            .unwrap_or_else(|| Symbol::intern(self.get_token_value(token)))
+    }
+
     fn get_token_string_value(&self, token: &Token) -> &'a str {
         &self.source[token.offset + 1..token.end - 1]
     }
 
-    fn evaluate_binary_addition(&self, left: &Object, right: &Object) -> Object {
-        match (left, right) {
-            (Object::String(rhs), Object::String(lhs)) => {
-                let mut result = rhs.to_owned();
-                result.push_str(lhs);
-                Object::String(result)
-            }
-            (Object::Number(rhs), Object::Number(lhs)) => Object::Number(rhs + lhs),
-            _ => panic!("Operator + operands must be strings or numbers"),
+    fn binary(&self, left: &Object, operator: TokenKind, right: &Object) -> Result<Object> {
+        if operator == TokenKind::Plus {
+            self.context.budget.check_concatenation(left, right)?;
         }
+
+        operators::binary(left, operator, right)
     }
 }
 
-impl StatementVisitor<Object> for StatementInterpreter<'_> {
-    fn handle_literal_expression(&mut self, literal: &Literal) -> Object {
-        if literal.token.kind == TokenKind::String {
-            let value = self.get_token_string_value(&literal.token);
-            Object::String(value.to_string())
-        } else if literal.token.kind == TokenKind::Number {
-            let value = self.get_token_value(&literal.token);
-            Object::Number(value.parse::<f32>().unwrap())
-        } else if literal.token.kind == TokenKind::True {
-            Object::Boolean(true)
-        } else if literal.token.kind == TokenKind::False {
-            Object::Boolean(false)
-        } else {
-            panic!("Unexpected literal type {:?}", literal.token.kind)
+impl StatementVisitor<Result<Object>> for StatementInterpreter<'_> {
+    fn handle_literal_expression(&mut self, literal: &Literal) -> Result<Object> {
+        Ok(Object::from(&literal.constant(self.source)))
+    }
+
+    fn handle_binary_expression(&mut self, binary: &Binary) -> Result<Object> {
+        let left = self.evaluate(binary.left)?;
+        if binary.operator.kind == TokenKind::QuestionQuestion {
+            return match left {
+                Object::None => self.evaluate(binary.right),
+                _ => Ok(left),
+            };
         }
+
+        let right = self.evaluate(binary.right)?;
+
+        self.binary(&left, binary.operator.kind, &right)
+            .map_err(|error| error.or_span(binary.operator.span()))
+    }
+
+    fn handle_grouping_expression(&mut self, index: usize) -> Result<Object> {
+        self.evaluate(index)
+    }
+
+    fn handle_unary_expression(&mut self, unary: &Unary) -> Result<Object> {
+        let right = self.evaluate(unary.right)?;
+
+        operators::unary(unary.operator.kind, &right)
+            .map_err(|error| error.or_span(unary.operator.span()))
+    }
+
+    fn handle_variable_expression(&mut self, variable: &Token) -> Result<Object> {
+        let name = self.get_symbol(variable);
+        self.context
+            .lookup_variable(name)
+            .map_err(|error| error.or_span(variable.span()))
     }
 
-    fn handle_binary_expression(&mut self, binary: &Binary) -> Object {
-        let left = self.evaluate(binary.left);
-        let right = self.evaluate(binary.right);
+    fn handle_assignment_expression(&mut self, assignment: &Assignment) -> Result<Object> {
+        let name = self.get_symbol(&assignment.target);
+        let right = self.evaluate(assignment.value)?;
 
-        match binary.operator.kind {
-            TokenKind::Plus => self.evaluate_binary_addition(&left, &right),
-            TokenKind::Greater => Object::Boolean(left.number() > right.number()),
-            TokenKind::GreaterEqual => Object::Boolean(left.number() >= right.number()),
-            TokenKind::Less => Object::Boolean(left.number() < right.number()),
-            TokenKind::LessEqual => Object::Boolean(left.number() <= right.number()),
-            TokenKind::BangEqual => Object::Boolean(!left.equals(&right)),
-            TokenKind::EqualEqual => Object::Boolean(left.equals(&right)),
-            TokenKind::Minus => Object::Number(left.number() - right.number()),
-            TokenKind::Slash => Object::Number(left.number() / right.number()),
-            TokenKind::Star => Object::Number(left.number() * right.number()),
-            _ => panic!("Unexpected binary operator {:?}", binary.operator.kind),
+        let value = match assignment.operator.kind.compound_operator() {
+            Some(operator) => self
+                .context
+                .lookup_variable(name)
+                .and_then(|left| self.binary(&left, operator, &right)),
+            None => Ok(right),
+        };
+
+        value
+            .and_then(|value| {
+                self.context.assign_variable(name, &value)?;
+                Ok(value)
+            })
+            .map_err(|error| error.or_span(assignment.operator.span()))
+    }
+
+    fn handle_update_expression(&mut self, update: &Update) -> Result<Object> {
+        let name = self.get_symbol(&update.target);
+
+        let previous = self
+            .context
+            .lookup_variable(name)
+            .and_then(|value| value.number())
+            .map_err(|error| error.or_span(update.operator.span()))?;
+
+        let value = match update.operator.kind {
+            TokenKind::PlusPlus => previous + 1.0,
+            TokenKind::MinusMinus => previous - 1.0,
+            _ => panic!("Unexpected update operator {:?}", update.operator.kind),
+        };
+
+        self.context
+            .assign_variable(name, &Object::Number(value))
+            .map_err(|error| error.or_span(update.operator.span()))?;
+
+        if update.prefix {
+            Ok(Object::Number(value))
+        } else {
+            Ok(Object::Number(previous))
         }
     }
 
-    fn handle_grouping_expression(&mut self, index: usize) -> Object {
-        self.evaluate(index)
+    fn handle_interpolation_expression(&mut self, parts: &[usize]) -> Result<Object> {
+        let mut result = String::new();
+
+        for part in parts {
+            let value = self.evaluate(*part)?;
+            result.push_str(&value.str());
+        }
+
+        Ok(Object::String(result))
     }
 
-    fn handle_unary_expression(&mut self, unary: &Unary) -> Object {
-        let right = self.evaluate(unary.right);
+    fn handle_call_expression(&mut self, call: &Call) -> Result<Object> {
+        let callee = self.evaluate(call.callee)?;
+        if call.optional && matches!(callee, Object::None) {
+            return Ok(Object::None);
+        }
 
-        match unary.operator.kind {
-            TokenKind::Minus => Object::Number(-right.number()),
-            TokenKind::Bang => Object::Boolean(!right.boolean()),
-            _ => panic!("Unexpected unary operator {:?}", unary.operator.kind),
+        let mut arguments = Vec::with_capacity(call.arguments.len());
+        for argument in call.arguments.iter() {
+            arguments.push(self.evaluate(*argument)?);
         }
+
+        let result = match callee {
+            Object::NativeFunction(function) => {
+                self.context.call_stack.push(CallFrame {
+                    name: function.name.clone(),
+                    path: None,
+                });
+                let result = function.call(&arguments);
+                self.context.call_stack.pop();
+
+                result.map_err(|error| {
+                    error.with_frame(TraceFrame {
+                        name: function.name.clone(),
+                        location: None,
+                        native: true,
+                    })
+                })
+            }
+            _ => Err(Error::runtime(format!(
+                "Can only call functions, got {}",
+                callee.type_name()
+            ))),
+        };
+
+        result.map_err(|error| error.or_span(call.paren.span()))
     }
 
-    fn handle_variable_expression(&mut self, variable: &Token) -> Object {
-        let name = self.get_token_value(variable);
-        self.context.lookup_variable(name)
+    fn handle_get_expression(&mut self, get: &Get) -> Result<Object> {
+        let object = self.evaluate(get.object)?;
+        let name = self.get_symbol(&get.name);
+
+        let result = match object {
+            Object::None if get.optional => Ok(Object::None),
+            Object::Module(module) => module.exports.get(&name).cloned().ok_or_else(|| {
+                Error::runtime(format!("Module {} has no export {}", module.name(), name))
+            }),
+            Object::Error(exception) => exception.get(name.as_str()),
+            _ => Err(Error::runtime(format!(
+                "Only modules and errors have properties, got {}",
+                object.type_name()
+            ))),
+        };
+
+        result.map_err(|error| error.or_span(get.name.span()))
     }
 
-    fn handle_variable_definition_statement(&mut self, declaration: &VariableDefinition) -> Object {
-        let name = self.get_token_value(&declaration.identifier);
-        let value = self.evaluate(declaration.expression);
+    fn handle_constant_expression(&mut self, constant: &Constant) -> Result<Object> {
+        Ok(Object::from(constant))
+    }
+
+    fn handle_variable_definition_statement(
+        &mut self,
+        declaration: &VariableDefinition,
+    ) -> Result<Object> {
+        let name = self.get_symbol(&declaration.identifier);
+        let value = self.evaluate(declaration.expression)?;
         self.context.add_variable(name, &value);
 
-        Object::None
+        Ok(Object::None)
+    }
+
+    fn handle_print_statement(&mut self, expression: usize) -> Result<Object> {
+        let value = self.evaluate(expression)?;
+        writeln!(self.context.streams.borrow_mut().output, "{}", value.str())?;
+
+        Ok(Object::None)
+    }
+
+    fn handle_import_statement(&mut self, import: &Import) -> Result<Object> {
+        let path = self.get_token_string_value(&import.path);
+        let module = self
+            .context
+            .import_module(path)
+            .map_err(|error| error.or_span(import.path.span()))?;
+
+        let alias = self.get_symbol(&import.alias);
+        self.context.add_variable(alias, &Object::Module(module));
+
+        Ok(Object::None)
+    }
+
+    fn handle_export_statement(&mut self, declaration: usize) -> Result<Object> {
+        self.evaluate(declaration)?;
+
+        if let Some(StatementNode::VariableDefinition(definition)) = self.tree.tree.get(declaration)
+        {
+            let name = self.get_symbol(&definition.identifier);
+            self.context.modules.export(name);
+        }
+
+        Ok(Object::None)
+    }
+
+    fn handle_block_statement(&mut self, statements: &[usize]) -> Result<Object> {
+        for statement in statements {
+            self.evaluate(*statement)?;
+        }
+
+        Ok(Object::None)
+    }
+
+    fn handle_try_statement(&mut self, statement: &Try) -> Result<Object> {
+        let mut result = self.evaluate(statement.body);
+        let mut thrown = self.take_thrown(&result);
+
+        if let (Some(handler), Err(error)) = (&statement.handler, &result) {
+            if matches!(error.kind, ErrorKind::Runtime | ErrorKind::Thrown) {
+                let value = thrown
+                    .take()
+                    .unwrap_or_else(|| Object::Error(Rc::new(Exception::new(error, self.source))));
+
+                let name = self.get_symbol(&handler.variable);
+                self.context.add_variable(name, &value);
+
+                result = self.evaluate(handler.body);
+                thrown = self.take_thrown(&result);
+            }
+        }
+
+        // Limit errors and aborts stop the program, so they skip the finally
+        // block too.
+        let aborted = matches!(
+            &result,
+            Err(error) if matches!(error.kind, ErrorKind::Limit | ErrorKind::Aborted)
+        );
+        if let (Some(finalizer), false) = (statement.finalizer, aborted) {
+            self.evaluate(finalizer)?;
+        }
+
+        self.context.thrown = thrown;
+        result.map(|_| Object::None)
     }
 
-    fn handle_print_statement(&mut self, expression: usize) -> Object {
-        let value = self.evaluate(expression);
-        println!("{:?}", value);
+    fn handle_throw_statement(&mut self, expression: usize) -> Result<Object> {
+        let value = self.evaluate(expression)?;
 
-        Object::None
+        let error = match &value {
+            Object::Error(exception) => Error {
+                kind: ErrorKind::Thrown,
+                message: exception.message.clone(),
+                span: exception.span,
+                trace: Vec::new(),
+            },
+            _ => Error::thrown(value.str(), self.tree.spans[expression]),
+        };
+
+        self.context.thrown = Some(value);
+        Err(error)
     }
 }
 
 #[derive(Default)]
-struct Frame {
-    pub variables: HashMap<String, Object>,
+pub struct Frame {
+    pub variables: HashMap<Symbol, Object>,
+}
+
+/// A script, module or native function on the call stack. Native functions
+/// and sources that were not read from a file have no path.
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub name: String,
+    pub path: Option<PathBuf>,
+}
+
+impl CallFrame {
+    fn script(path: Option<PathBuf>) -> Self {
+        Self {
+            name: "<script>".to_string(),
+            path,
+        }
+    }
 }
 
 pub struct ProgramContext {
     stack_frames: Vec<Frame>,
+    streams: Rc<RefCell<Streams>>,
+    budget: Budget,
+    hook: Option<Box<dyn Hook>>,
+    modules: ModuleLoader,
+    thrown: Option<Object>,
+    call_stack: Vec<CallFrame>,
+    sources: SourceRegistry,
 }
 
 impl Default for ProgramContext {
@@ -181,36 +472,796 @@ impl Default for ProgramContext {
 
 impl ProgramContext {
     pub fn new() -> Self {
-        Self {
+        Self::with_streams(Streams::default())
+    }
+
+    pub fn with_streams(streams: Streams) -> Self {
+        let mut context = Self {
             stack_frames: vec![Frame::default()],
+            streams: Rc::new(RefCell::new(streams)),
+            budget: Budget::default(),
+            hook: None,
+            modules: ModuleLoader::default(),
+            thrown: None,
+            call_stack: Vec::new(),
+            sources: SourceRegistry::default(),
+        };
+
+        for function in native::builtins(&context.streams) {
+            context.add_function(function);
         }
+
+        context
+    }
+
+    /// Interprets source that is dropped from the registry once it has run, so
+    /// hosts evaluating many small inputs do not accumulate them.
+    pub fn interpret(&mut self, source: &str) -> Result<Object> {
+        let id = self.sources.add("<input>", source);
+
+        self.budget.start();
+        let result = self.execute(CallFrame::script(None), id);
+        self.sources.remove(id);
+
+        result
+    }
+
+    /// Interprets source that did not come from a file, such as a REPL entry,
+    /// and keeps it for the session under a name that diagnostics use in
+    /// place of a path.
+    pub fn interpret_named(&mut self, name: &str, source: &str) -> Result<Object> {
+        let id = self.sources.add(name, source);
+
+        self.budget.start();
+        self.execute(CallFrame::script(None), id)
     }
 
-    pub fn interpret(&mut self, source: &str) {
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+    /// Interprets the source of a script file, so its imports resolve
+    /// relative to the file and importing it back is reported as circular.
+    pub fn interpret_file(&mut self, path: impl AsRef<Path>, source: &str) -> Result<Object> {
+        let path = path.as_ref();
+        self.modules.enter(path.canonicalize()?)?;
+        let id = self.sources.add(path.display().to_string(), source);
 
+        self.budget.start();
+        let result = self.execute(CallFrame::script(Some(path.to_path_buf())), id);
+        self.modules.exit();
+
+        result
+    }
+
+    /// Runs a script or module source in its own call frame, recording where
+    /// it was executing in the trace of an error that leaves it. The span of
+    /// such an error is cleared, the caller fills in its own position.
+    fn execute(&mut self, frame: CallFrame, id: SourceId) -> Result<Object> {
+        self.call_stack.push(frame);
+        let result = self.run(id);
+        let frame = self.call_stack.pop().unwrap();
+
+        result.map_err(|error| {
+            let frame = TraceFrame {
+                name: frame.name,
+                location: error.span.and_then(|span| self.sources.location(span)),
+                native: false,
+            };
+
+            let error = error.with_frame(frame);
+            if self.call_stack.is_empty() {
+                error
+            } else {
+                Error {
+                    span: None,
+                    ..error
+                }
+            }
+        })
+    }
+
+    /// The scripts, modules and native functions currently executing,
+    /// outermost first.
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    /// The files, modules and named inputs this context has run, which the
+    /// spans of its errors refer to.
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
+    }
+
+    /// Runs source without a call frame of its own, as the debugger does to
+    /// evaluate expressions in the paused program.
+    pub fn inspect(&mut self, source: &str) -> Result<Object> {
+        let id = self.sources.add("<inspect>", source);
+        let result = self.run(id);
+        self.sources.remove(id);
+
+        result
+    }
+
+    fn run(&mut self, id: SourceId) -> Result<Object> {
+        let file = self.sources.get(id).unwrap();
+        let (source, name) = (file.text.clone(), file.name.clone());
+        let source = &*source;
+
+        let tokens = lexer::tokenize_source(source, id)?;
+        let mut statements = parser::parse(tokens)?;
+
+        // Hooks observe the program as written, so it is only optimized when
+        // nobody is stepping through or profiling it.
+        if self.hook.is_none() {
+            for statement in statements.iter_mut() {
+                optimizer::optimize(statement, source);
+            }
+        }
+
+        let mut result = Object::None;
         for statement in statements.iter() {
-            let mut interpreter = StatementInterpreter::new(statement, source, self);
-            interpreter.interpret();
+            let mut interpreter = StatementInterpreter::new(statement, source, &name, self);
+            result = interpreter.interpret()?;
         }
+
+        Ok(result)
+    }
+
+    /// Type checks the source against the variables currently in scope,
+    /// without running it.
+    pub fn check(&self, source: &str) -> Result<Vec<Error>> {
+        let mut checker = TypeChecker::new();
+        for (name, value) in self.stack_frames.last().unwrap().variables.iter() {
+            checker.declare(name.as_str(), Type::of(value));
+        }
+
+        checker.check(source)
+    }
+
+    pub fn set_hook(&mut self, hook: Option<Box<dyn Hook>>) {
+        self.hook = hook;
+    }
+
+    pub fn stack_frames(&self) -> &[Frame] {
+        &self.stack_frames
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.budget.limits()
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.budget = Budget::new(limits);
+    }
+
+    pub fn streams(&self) -> Rc<RefCell<Streams>> {
+        self.streams.clone()
+    }
+
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        arity: usize,
+        callback: impl Fn(&Arguments) -> Result<Object> + 'static,
+    ) {
+        self.add_function(NativeFunction::new(name, arity, callback));
+    }
+
+    /// Registers a function whose parameter and return types are known to
+    /// the type checker.
+    pub fn register_typed_function(
+        &mut self,
+        name: &str,
+        parameters: &[Type],
+        returns: Type,
+        callback: impl Fn(&Arguments) -> Result<Object> + 'static,
+    ) {
+        let function = NativeFunction::new(name, parameters.len(), callback)
+            .with_signature(parameters.to_vec(), returns);
+        self.add_function(function);
     }
 
-    pub fn add_variable(&mut self, name: &str, value: &Object) {
+    pub fn set_global(&mut self, name: &str, value: impl Into<Object>) {
+        self.stack_frames
+            .first_mut()
+            .unwrap()
+            .variables
+            .insert(Symbol::intern(name), value.into());
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.stack_frames
+            .first()
+            .unwrap()
+            .variables
+            .get(&Symbol::lookup(name)?)
+            .cloned()
+    }
+
+    pub fn add_variable(&mut self, name: Symbol, value: &Object) {
         self.stack_frames
             .last_mut()
             .unwrap()
             .variables
-            .insert(name.to_string(), value.clone());
+            .insert(name, value.clone());
     }
 
-    pub fn lookup_variable(&self, name: &str) -> Object {
+    pub fn assign_variable(&mut self, name: Symbol, value: &Object) -> Result<()> {
+        let variable = self
+            .stack_frames
+            .last_mut()
+            .unwrap()
+            .variables
+            .get_mut(&name)
+            .ok_or_else(|| Error::runtime(format!("Undeclared variable {}", name)))?;
+
+        *variable = value.clone();
+        Ok(())
+    }
+
+    pub fn lookup_variable(&self, name: Symbol) -> Result<Object> {
         self.stack_frames
             .last()
             .unwrap()
             .variables
-            .get(name)
-            .unwrap_or_else(|| panic!("Undeclared variable {}", name))
-            .clone()
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| Error::runtime(format!("Undeclared variable {}", name)))
+    }
+
+    /// Executes a module once in its own global frame holding only the native
+    /// functions, later imports of the same file share the cached exports.
+    fn import_module(&mut self, path: &str) -> Result<Rc<Module>> {
+        let path = self.modules.resolve(path)?;
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module);
+        }
+
+        let source = fs::read_to_string(&path).map_err(|error| {
+            Error::runtime(format!("Cannot read module {}: {}", path.display(), error))
+        })?;
+
+        let natives = self.stack_frames[0]
+            .variables
+            .iter()
+            .filter(|(_, value)| matches!(value, Object::NativeFunction(_)))
+            .map(|(name, value)| (*name, value.clone()))
+            .collect();
+
+        self.modules.enter(path.clone())?;
+        let frames = mem::replace(&mut self.stack_frames, vec![Frame { variables: natives }]);
+
+        let frame = CallFrame {
+            name: format!("<module {}>", Module::stem(&path)),
+            path: Some(path.clone()),
+        };
+        let id = self.sources.add(path.display().to_string(), &source);
+        let result = self.execute(frame, id);
+
+        let frames = mem::replace(&mut self.stack_frames, frames);
+        let names = self.modules.exit();
+
+        result?;
+
+        let variables = &frames[0].variables;
+        let exports = names
+            .into_iter()
+            .filter_map(|name| variables.get(&name).map(|value| (name, value.clone())))
+            .collect();
+
+        let module = Rc::new(Module { path, exports });
+        self.modules.insert(module.clone());
+
+        Ok(module)
+    }
+
+    fn add_function(&mut self, function: NativeFunction) {
+        let name = function.name.clone();
+        self.set_global(&name, Object::NativeFunction(Rc::new(function)));
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::io::{SharedBuffer, Streams};
+
+    #[allow(unused_imports)]
+    use super::limits::Limits;
+
+    #[allow(unused_imports)]
+    use super::{Object, ProgramContext};
+
+    #[allow(unused_imports)]
+    use crate::interner::Symbol;
+
+    #[allow(unused_imports)]
+    use std::time::Duration;
+
+    #[allow(unused_imports)]
+    use crate::error::ErrorKind;
+
+    #[allow(dead_code)]
+    fn evaluate(program: &mut ProgramContext, source: &str) -> String {
+        program.interpret(source).unwrap().str()
+    }
+
+    #[allow(dead_code)]
+    fn write_modules(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join(format!("lang-{}-{}", name, std::process::id()));
+        for (path, source) in files {
+            let path = directory.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+
+        directory
+    }
+
+    #[test]
+    fn should_format_values_for_display() {
+        assert_eq!(Object::String(String::from("hi")).str(), "hi");
+        assert_eq!(Object::Number(3.0).str(), "3");
+        assert_eq!(Object::Number(2.5).str(), "2.5");
+        assert_eq!(Object::Boolean(true).str(), "true");
+        assert_eq!(Object::None.str(), "nil");
+    }
+
+    #[test]
+    fn should_format_values_for_repr() {
+        assert_eq!(Object::String(String::from("hi")).repr(), "\"hi\"");
+        assert_eq!(Object::Number(3.0).repr(), "3");
+        assert_eq!(Object::None.repr(), "nil");
+    }
+
+    #[test]
+    fn should_stringify_concatenation_and_interpolation() {
+        let mut program = ProgramContext::new();
+
+        assert_eq!(evaluate(&mut program, "\"a\" + 1;"), "a1");
+        assert_eq!(evaluate(&mut program, "false + \"b\";"), "falseb");
+        assert_eq!(evaluate(&mut program, "\"${1.5}/${2}\";"), "1.5/2");
+        assert_eq!(evaluate(&mut program, "str(2) + str(nil);"), "2nil");
+    }
+
+    #[test]
+    fn should_coalesce_nil_values() {
+        let mut program = ProgramContext::new();
+        program.interpret("var a = nil; var b = 0;").unwrap();
+
+        assert_eq!(evaluate(&mut program, "a ?? 1;"), "1");
+        assert_eq!(evaluate(&mut program, "b ?? 1;"), "0");
+        assert_eq!(evaluate(&mut program, "a ?? nil ?? \"c\";"), "c");
+        assert_eq!(evaluate(&mut program, "a == nil;"), "true");
+        assert_eq!(evaluate(&mut program, "b == nil;"), "false");
+        assert_eq!(evaluate(&mut program, "!nil;"), "true");
+    }
+
+    #[test]
+    fn should_short_circuit_optional_property_access_on_nil() {
+        let mut program = ProgramContext::new();
+        program
+            .interpret(
+                "var a = nil; var e = nil; try { throw 1 - \"x\"; } catch (error) { e = error; }",
+            )
+            .unwrap();
+
+        assert_eq!(evaluate(&mut program, "a?.b;"), "nil");
+        assert_eq!(evaluate(&mut program, "a?.b?.c ?? 1;"), "1");
+        assert_eq!(evaluate(&mut program, "a?.f(1);"), "nil");
+        assert_eq!(evaluate(&mut program, "a?.b.c;"), "nil");
+        assert_eq!(evaluate(&mut program, "a?.f(x).g;"), "nil");
+        assert_eq!(evaluate(&mut program, "e?.line;"), "1");
+
+        let error = program.interpret("a.b;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Only modules and errors have properties, got nil"
+        );
+        let error = program.interpret("1?.b;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Only modules and errors have properties, got number"
+        );
+    }
+
+    #[test]
+    fn should_treat_values_of_different_types_as_unequal() {
+        let mut program = ProgramContext::new();
+
+        assert_eq!(evaluate(&mut program, "1 == \"1\";"), "false");
+        assert_eq!(evaluate(&mut program, "1 != \"1\";"), "true");
+        assert_eq!(evaluate(&mut program, "true == 1;"), "false");
+        assert_eq!(evaluate(&mut program, "\"a\" == \"a\";"), "true");
+    }
+
+    #[test]
+    fn should_order_strings_lexicographically() {
+        let mut program = ProgramContext::new();
+
+        assert_eq!(evaluate(&mut program, "\"apple\" < \"banana\";"), "true");
+        assert_eq!(evaluate(&mut program, "\"b\" >= \"ab\";"), "true");
+        assert_eq!(evaluate(&mut program, "\"a\" <= \"a\";"), "true");
+        assert_eq!(evaluate(&mut program, "\"a\" > \"a\";"), "false");
+    }
+
+    #[test]
+    fn should_reject_incomparable_operands() {
+        let mut program = ProgramContext::new();
+        let error = program.interpret("\"a\" < 1;").unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Runtime);
+        assert_eq!(error.message, "Cannot compare string with number");
+        assert_eq!(error.span.map(|span| span.offset), Some(4));
+    }
+
+    #[test]
+    fn should_not_evaluate_right_side_of_coalesce_when_left_is_present() {
+        let mut program = ProgramContext::new();
+        program.interpret("var a = 1; var b = 0;").unwrap();
+        program.interpret("a ?? b++;").unwrap();
+
+        assert_eq!(evaluate(&mut program, "b;"), "0");
+    }
+
+    #[test]
+    fn should_call_registered_native_functions() {
+        let mut program = ProgramContext::new();
+        program.register_function("add", 2, |arguments| {
+            let left = arguments.get::<f32>(0)?;
+            let right = arguments.get::<f32>(1)?;
+            Ok(Object::from(left + right))
+        });
+
+        assert_eq!(evaluate(&mut program, "add(1, add(2, 3));"), "6");
+    }
+
+    #[test]
+    fn should_reject_native_calls_with_wrong_arguments() {
+        let mut program = ProgramContext::new();
+        program.register_function("shout", 1, |arguments| {
+            let text = arguments.get::<String>(0)?;
+            Ok(Object::from(text.to_uppercase()))
+        });
+
+        let error = program.interpret("shout(1);").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Argument 1 of shout must be string, got number"
+        );
+
+        let error = program.interpret("shout();").unwrap_err();
+        assert_eq!(error.message, "shout expected 1 arguments but got 0");
+
+        let error = program.interpret("1();").unwrap_err();
+        assert_eq!(error.message, "Can only call functions, got number");
+    }
+
+    #[test]
+    fn should_share_globals_with_host() {
+        let mut program = ProgramContext::new();
+        program.set_global("limit", 10);
+
+        program.interpret("var doubled = limit * 2;").unwrap();
+
+        let doubled = program.get_global("doubled").unwrap();
+        assert_eq!(doubled.str(), "20");
+        assert!(program.get_global("missing_global").is_none());
+        assert_eq!(Symbol::lookup("missing_global"), None);
+    }
+
+    #[test]
+    fn should_write_to_provided_streams() {
+        let output = SharedBuffer::new();
+        let error = SharedBuffer::new();
+        let input = std::io::Cursor::new("first\nsecond\n");
+
+        let streams = Streams::new(output.clone(), error.clone(), input);
+        let mut program = ProgramContext::with_streams(streams);
+
+        program
+            .interpret("print \"hi\"; print input() + \"!\"; eprint(input()); print input();")
+            .unwrap();
+
+        assert_eq!(output.contents(), "hi\nfirst!\nnil\n");
+        assert_eq!(error.contents(), "second\n");
+    }
+
+    #[test]
+    fn should_abort_evaluation_when_limits_are_exceeded() {
+        let mut program = ProgramContext::new();
+        program.interpret("var a = \"ab\"; var n = 1;").unwrap();
+
+        let limits = Limits {
+            max_steps: Some(5),
+            max_depth: Some(4),
+            max_string_length: Some(4),
+            timeout: None,
+        };
+        program.set_limits(limits);
+
+        let error = program.interpret("n + n + n + n;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+        assert_eq!(error.message, "Step limit exceeded");
+
+        let error = program.interpret("~(~(~(~n)));").unwrap_err();
+        assert_eq!(error.message, "Depth limit exceeded");
+
+        let error = program.interpret("a + a + a;").unwrap_err();
+        assert_eq!(error.message, "String length limit exceeded");
+
+        let error = program.interpret("a + 123;").unwrap_err();
+        assert_eq!(error.message, "String length limit exceeded");
+
+        assert_eq!(evaluate(&mut program, "a + a;"), "abab");
+    }
+
+    #[test]
+    fn should_evaluate_the_deepest_nesting_the_parser_accepts() {
+        let mut program = ProgramContext::new();
+        let nested = |depth: usize| format!("{}1{};", "(-".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(evaluate(&mut program, &nested(63)), "-1");
+
+        let error = program.interpret(&nested(64)).unwrap_err();
+        assert_eq!(error.message, "Nesting limit exceeded");
+    }
+
+    #[test]
+    fn should_abort_evaluation_after_deadline() {
+        let mut program = ProgramContext::new();
+        program.set_limits(Limits {
+            timeout: Some(Duration::ZERO),
+            ..Limits::default()
+        });
+
+        let error = program.interpret("1;").unwrap_err();
+        assert_eq!(error.message, "Time limit exceeded");
+
+        program.set_limits(Limits::default());
+        assert_eq!(evaluate(&mut program, "1;"), "1");
+    }
+
+    #[test]
+    fn should_keep_context_usable_after_runtime_error() {
+        let mut program = ProgramContext::new();
+        program.interpret("var a = 1;").unwrap();
+
+        let error = program.interpret("a = b;").unwrap_err();
+        assert_eq!(error.message, "Undeclared variable b");
+
+        assert_eq!(evaluate(&mut program, "a;"), "1");
+    }
+
+    #[test]
+    fn should_return_results_for_non_ascii_input() {
+        let mut program = ProgramContext::new();
+
+        assert_eq!(evaluate(&mut program, "# café\n\"é${1}\" + \"ü\";"), "é1ü");
+        assert_eq!(evaluate(&mut program, "var s = \"ö\"; s;"), "ö");
+
+        let error = program.interpret("\"é\" - 1;").unwrap_err();
+        assert_eq!(error.span.map(|span| span.offset), Some(5));
+
+        let error = program.interpret("print \"é;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Syntax);
+    }
+
+    #[test]
+    fn should_import_exported_values_once() {
+        let directory = write_modules(
+            "import",
+            &[
+                ("main.lang", "import \"lib/a.lang\" as a; import \"lib/a.lang\" as again; print a.answer + again.twice;"),
+                ("lib/a.lang", "import \"b.lang\" as b; print \"loading a\"; export var answer = b.base * 2; export var twice = answer * 2; var hidden = 1;"),
+                ("lib/b.lang", "export var base = 21;"),
+            ],
+        );
+
+        let output = SharedBuffer::new();
+        let streams = Streams::new(output.clone(), std::io::sink(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+
+        let main = directory.join("main.lang");
+        let source = std::fs::read_to_string(&main).unwrap();
+        program.interpret_file(&main, &source).unwrap();
+
+        assert_eq!(output.contents(), "loading a\n126\n");
+
+        let error = program.interpret("a.hidden;").unwrap_err();
+        assert_eq!(error.message, "Module a has no export hidden");
+        assert_eq!(error.span.map(|span| span.offset), Some(2));
+
+        let error = program.interpret("var n = 1; n.x;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "Only modules and errors have properties, got number"
+        );
+    }
+
+    #[test]
+    fn should_report_circular_imports() {
+        let directory = write_modules(
+            "circular",
+            &[
+                ("a.lang", "import \"b.lang\" as b;"),
+                ("b.lang", "var x = 1;\nimport \"a.lang\" as a;"),
+            ],
+        );
+
+        let mut program = ProgramContext::new();
+        let main = directory.join("a.lang");
+        let error = program
+            .interpret_file(&main, "import \"b.lang\" as b;")
+            .unwrap_err();
+
+        let a = main.canonicalize().unwrap();
+        let b = directory.join("b.lang").canonicalize().unwrap();
+        assert_eq!(
+            error.message,
+            format!(
+                "Circular import {} -> {} -> {}",
+                a.display(),
+                b.display(),
+                a.display()
+            )
+        );
+        assert_eq!(error.span.map(|span| span.offset), Some(7));
+
+        let locations = error
+            .trace
+            .iter()
+            .map(|frame| {
+                let location = frame.location.as_ref().unwrap();
+                format!(
+                    "{} {}:{} {}",
+                    frame.name, location.line, location.column, location.text
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            vec![
+                "<module b> 2:8 import \"a.lang\" as a;",
+                "<script> 1:8 import \"b.lang\" as b;"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_catch_thrown_values_and_run_finally() {
+        let output = SharedBuffer::new();
+        let streams = Streams::new(output.clone(), SharedBuffer::new(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+
+        let source =
+            "try { print 1; throw \"boom\"; print 2; } catch (e) { print e; } finally { print 3; }
+            try { try { throw 4; } finally { print 5; } } catch (e) { print e + 1; }";
+        program.interpret(source).unwrap();
+
+        assert_eq!(output.contents(), "1\nboom\n3\n5\n5\n");
+    }
+
+    #[test]
+    fn should_catch_runtime_errors_as_error_values() {
+        let mut program = ProgramContext::new();
+        program
+            .interpret("var m = nil;\ntry {\n  var x = 1 // 0;\n} catch (e) { m = e; }")
+            .unwrap();
+
+        assert_eq!(evaluate(&mut program, "m.message;"), "Division by zero");
+        assert_eq!(evaluate(&mut program, "m.line + m.column;"), "16");
+        assert_eq!(evaluate(&mut program, "m;"), "<error Division by zero>");
+
+        let error = program.interpret("throw m;").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Thrown);
+        assert_eq!(error.message, "Division by zero");
+        assert_eq!(error.span.map(|span| span.offset), Some(31));
+    }
+
+    #[test]
+    fn should_propagate_uncaught_exceptions_to_the_host() {
+        let mut program = ProgramContext::new();
+
+        let error = program
+            .interpret("print 1;\nthrow \"bad \" + 1;")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Thrown);
+        assert_eq!(error.message, "bad 1");
+        assert_eq!(error.span.map(|span| span.offset), Some(15));
+
+        let error = program
+            .interpret("try { throw 1; } catch (e) { undefined; }")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Runtime);
+        assert_eq!(error.message, "Undeclared variable undefined");
+
+        program.interpret("var n = 1; var caught = 0;").unwrap();
+        program.set_limits(Limits {
+            max_depth: Some(3),
+            ..Limits::default()
+        });
+        let error = program
+            .interpret("try { ~(~(~n)); } catch (e) { caught = 1; }")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Limit);
+        assert_eq!(program.get_global("caught").unwrap().str(), "0");
+    }
+
+    #[test]
+    fn should_trace_errors_through_modules_and_native_calls() {
+        let directory = write_modules(
+            "trace",
+            &[
+                ("main.lang", "var a = 1;\nimport \"lib/util.lang\" as util;"),
+                ("lib/util.lang", "print 1;\nprint str(1, 2);"),
+            ],
+        );
+
+        let mut program = ProgramContext::new();
+        let main = directory.join("main.lang");
+        let source = std::fs::read_to_string(&main).unwrap();
+        let error = program.interpret_file(&main, &source).unwrap_err();
+
+        assert_eq!(error.message, "str expected 1 arguments but got 2");
+        assert_eq!(error.span.map(|span| span.offset), Some(18));
+        assert!(program.call_stack().is_empty());
+
+        let util = directory.join("lib/util.lang").canonicalize().unwrap();
+        assert_eq!(
+            error.traceback(),
+            format!(
+                "Traceback (most recent call last):\n  at <script> ({}:2:8)\n    import \"lib/util.lang\" as util;\n  at <module util> ({}:2:10)\n    print str(1, 2);\n  at str (native)\n",
+                main.display(),
+                util.display()
+            )
+        );
+    }
+
+    #[test]
+    fn should_trace_script_frames_without_a_location() {
+        let mut program = ProgramContext::new();
+        program.interpret("var a = 1;").unwrap();
+        program.set_limits(Limits {
+            max_steps: Some(2),
+            ..Limits::default()
+        });
+
+        let error = program.interpret("a + a + a;").unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::Limit);
+        assert_eq!(
+            error.traceback(),
+            "Traceback (most recent call last):\n  at <script> (unknown location)\n"
+        );
+    }
+
+    #[test]
+    fn should_keep_every_source_and_name_it_in_diagnostics() {
+        let mut program = ProgramContext::new();
+        program.interpret_named("<repl:1>", "var a = 1;").unwrap();
+        let error = program
+            .interpret_named("<repl:2>", "print a;\nprint a - \"b\";")
+            .unwrap_err();
+
+        assert_eq!(program.sources().len(), 2);
+
+        let span = error.span.unwrap();
+        let location = program.sources().location(span).unwrap();
+        assert_eq!(location.path, "<repl:2>");
+        assert_eq!((location.line, location.column), (2, 9));
+        assert_eq!(location.text, "print a - \"b\";");
+
+        let source = &program.sources().get(span.source).unwrap().text;
+        assert_eq!(&source[span.offset..span.end], "-");
+    }
+
+    #[test]
+    fn should_not_keep_anonymous_sources() {
+        let mut program = ProgramContext::new();
+        for n in 0..100 {
+            program.interpret(&format!("var a = {};", n)).unwrap();
+            program.interpret("a - \"b\";").unwrap_err();
+            program.inspect("a;").unwrap();
+        }
+
+        assert!(program.sources().is_empty());
     }
 }
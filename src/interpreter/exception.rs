@@ -0,0 +1,37 @@
+use crate::error::{Error, Result};
+use crate::lexer::token::Span;
+use crate::source::LineIndex;
+
+use super::object::Object;
+
+/// The value a `catch` block receives for a runtime error raised by the
+/// interpreter itself, values passed to `throw` are caught unchanged.
+#[derive(Debug)]
+pub struct Exception {
+    pub message: String,
+    pub span: Option<Span>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl Exception {
+    pub fn new(error: &Error, source: &str) -> Self {
+        let lines = LineIndex::new(source);
+
+        Self {
+            message: error.message.clone(),
+            span: error.span,
+            line: error.span.map(|span| lines.line(span.offset)),
+            column: error.span.map(|span| lines.column(span.offset)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Object> {
+        match name {
+            "message" => Ok(Object::String(self.message.clone())),
+            "line" => Ok(self.line.map(|line| line as f32).into()),
+            "column" => Ok(self.column.map(|column| column as f32).into()),
+            _ => Err(Error::runtime(format!("Error has no property {}", name))),
+        }
+    }
+}
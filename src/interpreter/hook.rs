@@ -0,0 +1,30 @@
+use crate::error::Result;
+use crate::lexer::token::Span;
+
+use super::ProgramContext;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Location<'a> {
+    pub source: &'a str,
+    /// The name the source was registered under, such as a path or `<repl:2>`.
+    pub name: &'a str,
+    pub span: Span,
+    /// Whether the node is a whole statement rather than part of one.
+    pub statement: bool,
+    pub depth: usize,
+    pub function: Option<&'a str>,
+}
+
+pub trait Hook {
+    fn before_evaluate(
+        &mut self,
+        _context: &mut ProgramContext,
+        _location: Location,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn after_evaluate(&mut self, _context: &mut ProgramContext, _location: Location) -> Result<()> {
+        Ok(())
+    }
+}
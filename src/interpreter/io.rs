@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::io::{stderr, stdin, stdout, BufRead, BufReader, Write};
+use std::rc::Rc;
+
+pub struct Streams {
+    pub output: Box<dyn Write>,
+    pub error: Box<dyn Write>,
+    pub input: Box<dyn BufRead>,
+}
+
+impl Default for Streams {
+    fn default() -> Self {
+        // A single byte buffer keeps script reads from consuming lines meant for the REPL
+        Self::new(stdout(), stderr(), BufReader::with_capacity(1, stdin()))
+    }
+}
+
+impl Streams {
+    pub fn new(
+        output: impl Write + 'static,
+        error: impl Write + 'static,
+        input: impl BufRead + 'static,
+    ) -> Self {
+        Self {
+            output: Box::new(output),
+            error: Box::new(error),
+            input: Box::new(input),
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct SharedBuffer {
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SharedBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.borrow()).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
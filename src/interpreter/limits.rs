@@ -0,0 +1,102 @@
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+use super::object::Object;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Limits {
+    pub max_steps: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_string_length: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Default)]
+pub struct Budget {
+    limits: Limits,
+    steps: usize,
+    depth: usize,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn start(&mut self) {
+        self.steps = 0;
+        self.depth = 0;
+        self.deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    pub fn enter(&mut self) -> Result<()> {
+        self.steps += 1;
+        self.depth += 1;
+
+        if self.limits.max_steps.is_some_and(|max| self.steps > max) {
+            return Err(Error::limit("Step limit exceeded"));
+        }
+
+        if self.limits.max_depth.is_some_and(|max| self.depth > max) {
+            return Err(Error::limit("Depth limit exceeded"));
+        }
+
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Error::limit("Time limit exceeded"));
+        }
+
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub fn check(&self, value: &Object) -> Result<()> {
+        match value {
+            Object::String(value) => self.check_length(value.len()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects a concatenation before its result is allocated.
+    pub fn check_concatenation(&self, left: &Object, right: &Object) -> Result<()> {
+        match (left, right) {
+            (Object::String(left), Object::String(right)) => {
+                self.check_length(left.len() + right.len())
+            }
+            (Object::String(value), other) | (other, Object::String(value)) => {
+                self.check_length(value.len() + other.str().len())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_length(&self, length: usize) -> Result<()> {
+        if self
+            .limits
+            .max_string_length
+            .is_some_and(|max| length > max)
+        {
+            Err(Error::limit("String length limit exceeded"))
+        } else {
+            Ok(())
+        }
+    }
+}
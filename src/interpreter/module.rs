@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::interner::Symbol;
+
+use super::object::Object;
+
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub exports: HashMap<Symbol, Object>,
+}
+
+impl Module {
+    pub fn name(&self) -> String {
+        Self::stem(&self.path)
+    }
+
+    pub fn stem(path: &Path) -> String {
+        path.file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+    }
+}
+
+/// Keeps every module that finished executing, and the chain of modules
+/// currently executing so circular imports can be reported.
+#[derive(Default)]
+pub struct ModuleLoader {
+    modules: HashMap<PathBuf, Rc<Module>>,
+    loading: Vec<(PathBuf, Vec<Symbol>)>,
+}
+
+impl ModuleLoader {
+    /// Resolves an import relative to the directory of the importing file, or
+    /// to the working directory for code that does not come from a file.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf> {
+        let directory = match self.loading.last() {
+            Some((importer, _)) => importer.parent().map(Path::to_path_buf),
+            None => env::current_dir().ok(),
+        };
+
+        let path = directory.unwrap_or_default().join(path);
+        path.canonicalize().map_err(|error| {
+            Error::runtime(format!("Cannot find module {}: {}", path.display(), error))
+        })
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Rc<Module>> {
+        self.modules.get(path).cloned()
+    }
+
+    pub fn enter(&mut self, path: PathBuf) -> Result<()> {
+        if let Some(start) = self
+            .loading
+            .iter()
+            .position(|(loading, _)| *loading == path)
+        {
+            let chain = self.loading[start..]
+                .iter()
+                .map(|(loading, _)| loading.display().to_string())
+                .chain([path.display().to_string()])
+                .collect::<Vec<_>>();
+
+            return Err(Error::runtime(format!(
+                "Circular import {}",
+                chain.join(" -> ")
+            )));
+        }
+
+        self.loading.push((path, Vec::new()));
+        Ok(())
+    }
+
+    /// Returns the names exported by the module that just finished executing.
+    pub fn exit(&mut self) -> Vec<Symbol> {
+        self.loading
+            .pop()
+            .map_or_else(Vec::new, |(_, exports)| exports)
+    }
+
+    pub fn export(&mut self, name: Symbol) {
+        if let Some((_, exports)) = self.loading.last_mut() {
+            exports.push(name);
+        }
+    }
+
+    pub fn insert(&mut self, module: Rc<Module>) {
+        self.modules.insert(module.path.clone(), module);
+    }
+}
@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::checker::types::{Signature, Type};
+use crate::error::{Error, Result};
+
+use super::io::Streams;
+use super::object::{FromObject, Object};
+
+type NativeCallback = dyn Fn(&Arguments) -> Result<Object>;
+
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    pub signature: Rc<Signature>,
+    callback: Box<NativeCallback>,
+}
+
+impl NativeFunction {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        callback: impl Fn(&Arguments) -> Result<Object> + 'static,
+    ) -> Self {
+        let signature = Signature {
+            parameters: vec![Type::Any; arity],
+            returns: Type::Any,
+        };
+
+        Self {
+            name: name.to_string(),
+            arity,
+            signature: Rc::new(signature),
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Declares the parameter and return types the type checker assumes for
+    /// calls to this function, the arity follows the parameters.
+    pub fn with_signature(mut self, parameters: Vec<Type>, returns: Type) -> Self {
+        self.arity = parameters.len();
+        self.signature = Rc::new(Signature {
+            parameters,
+            returns,
+        });
+        self
+    }
+
+    pub fn call(&self, values: &[Object]) -> Result<Object> {
+        if values.len() != self.arity {
+            return Err(Error::runtime(format!(
+                "{} expected {} arguments but got {}",
+                self.name,
+                self.arity,
+                values.len()
+            )));
+        }
+
+        let arguments = Arguments {
+            name: &self.name,
+            values,
+        };
+
+        (self.callback)(&arguments)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+pub struct Arguments<'a> {
+    name: &'a str,
+    values: &'a [Object],
+}
+
+impl Arguments<'_> {
+    pub fn get<T: FromObject>(&self, index: usize) -> Result<T> {
+        let value = self.values.get(index).ok_or_else(|| {
+            Error::runtime(format!("{} has no argument {}", self.name, index + 1))
+        })?;
+
+        T::from_object(value).ok_or_else(|| {
+            Error::runtime(format!(
+                "Argument {} of {} must be {}, got {}",
+                index + 1,
+                self.name,
+                T::TYPE_NAME,
+                value.type_name()
+            ))
+        })
+    }
+
+    pub fn values(&self) -> &[Object] {
+        self.values
+    }
+}
+
+pub fn builtins(streams: &Rc<RefCell<Streams>>) -> Vec<NativeFunction> {
+    let input_streams = streams.clone();
+    let error_streams = streams.clone();
+
+    vec![
+        NativeFunction::new("str", 1, |arguments| {
+            let value = arguments.get::<Object>(0)?;
+            Ok(Object::String(value.str()))
+        })
+        .with_signature(vec![Type::Any], Type::String),
+        NativeFunction::new("input", 0, move |_| {
+            let mut line = String::new();
+            if input_streams.borrow_mut().input.read_line(&mut line)? == 0 {
+                return Ok(Object::None);
+            }
+
+            let length = line.trim_end_matches(['\r', '\n']).len();
+            line.truncate(length);
+
+            Ok(Object::String(line))
+        }),
+        NativeFunction::new("eprint", 1, move |arguments| {
+            let value = arguments.get::<Object>(0)?;
+            writeln!(error_streams.borrow_mut().error, "{}", value.str())?;
+            Ok(Object::None)
+        })
+        .with_signature(vec![Type::Any], Type::Nil),
+    ]
+}
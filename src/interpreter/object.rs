@@ -0,0 +1,220 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::parser::tree::Constant;
+
+use super::exception::Exception;
+use super::module::Module;
+use super::native::NativeFunction;
+
+#[derive(Debug, Clone)]
+pub enum Object {
+    String(String),
+    Boolean(bool),
+    Number(f32),
+    NativeFunction(Rc<NativeFunction>),
+    Module(Rc<Module>),
+    Error(Rc<Exception>),
+    None,
+}
+
+impl Object {
+    pub fn number(&self) -> Result<f32> {
+        match self {
+            Object::Number(value) => Ok(*value),
+            _ => Err(Error::runtime(format!(
+                "Cannot cast {} to number",
+                self.type_name()
+            ))),
+        }
+    }
+
+    pub fn integer(&self) -> Result<i64> {
+        let value = self.number()?;
+        if value.fract() != 0.0 {
+            return Err(Error::runtime(format!(
+                "Cannot use {} as an integer",
+                value
+            )));
+        }
+
+        Ok(value as i64)
+    }
+
+    pub fn boolean(&self) -> bool {
+        match self {
+            Object::Boolean(value) => *value,
+            Object::None => false,
+            _ => true,
+        }
+    }
+
+    pub fn str(&self) -> String {
+        self.to_string()
+    }
+
+    pub fn repr(&self) -> String {
+        match self {
+            Object::String(value) => format!("\"{}\"", value),
+            _ => self.str(),
+        }
+    }
+
+    pub fn equals(&self, rhs: &Object) -> bool {
+        match (self, rhs) {
+            (Object::Number(lhs), Object::Number(rhs)) => lhs == rhs,
+            (Object::String(lhs), Object::String(rhs)) => lhs == rhs,
+            (Object::Boolean(lhs), Object::Boolean(rhs)) => lhs == rhs,
+            (Object::NativeFunction(lhs), Object::NativeFunction(rhs)) => Rc::ptr_eq(lhs, rhs),
+            (Object::Module(lhs), Object::Module(rhs)) => Rc::ptr_eq(lhs, rhs),
+            (Object::Error(lhs), Object::Error(rhs)) => Rc::ptr_eq(lhs, rhs),
+            (Object::None, Object::None) => true,
+            _ => false,
+        }
+    }
+
+    pub fn compare(&self, rhs: &Object) -> Result<Option<Ordering>> {
+        match (self, rhs) {
+            (Object::Number(lhs), Object::Number(rhs)) => Ok(lhs.partial_cmp(rhs)),
+            (Object::String(lhs), Object::String(rhs)) => Ok(Some(lhs.cmp(rhs))),
+            _ => Err(Error::runtime(format!(
+                "Cannot compare {} with {}",
+                self.type_name(),
+                rhs.type_name()
+            ))),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::String(_) => "string",
+            Object::Boolean(_) => "boolean",
+            Object::Number(_) => "number",
+            Object::NativeFunction(_) => "function",
+            Object::Module(_) => "module",
+            Object::Error(_) => "error",
+            Object::None => "nil",
+        }
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Object::String(value) => write!(f, "{}", value),
+            Object::Boolean(value) => write!(f, "{}", value),
+            Object::Number(value) => write!(f, "{}", value),
+            Object::NativeFunction(function) => write!(f, "<native fn {}>", function.name),
+            Object::Module(module) => write!(f, "<module {}>", module.name()),
+            Object::Error(exception) => write!(f, "<error {}>", exception.message),
+            Object::None => write!(f, "nil"),
+        }
+    }
+}
+
+impl From<f32> for Object {
+    fn from(value: f32) -> Self {
+        Object::Number(value)
+    }
+}
+
+impl From<i32> for Object {
+    fn from(value: i32) -> Self {
+        Object::Number(value as f32)
+    }
+}
+
+impl From<bool> for Object {
+    fn from(value: bool) -> Self {
+        Object::Boolean(value)
+    }
+}
+
+impl From<String> for Object {
+    fn from(value: String) -> Self {
+        Object::String(value)
+    }
+}
+
+impl From<&str> for Object {
+    fn from(value: &str) -> Self {
+        Object::String(value.to_string())
+    }
+}
+
+impl From<()> for Object {
+    fn from(_: ()) -> Self {
+        Object::None
+    }
+}
+
+impl From<&Constant> for Object {
+    fn from(constant: &Constant) -> Self {
+        match constant {
+            Constant::Number(value) => Object::Number(*value),
+            Constant::String(value) => Object::String(value.clone()),
+            Constant::Boolean(value) => Object::Boolean(*value),
+            Constant::Nil => Object::None,
+        }
+    }
+}
+
+impl<T: Into<Object>> From<Option<T>> for Object {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Object::None, Into::into)
+    }
+}
+
+pub trait FromObject: Sized {
+    const TYPE_NAME: &'static str;
+
+    fn from_object(object: &Object) -> Option<Self>;
+}
+
+impl FromObject for Object {
+    const TYPE_NAME: &'static str = "any";
+
+    fn from_object(object: &Object) -> Option<Self> {
+        Some(object.clone())
+    }
+}
+
+impl FromObject for f32 {
+    const TYPE_NAME: &'static str = "number";
+
+    fn from_object(object: &Object) -> Option<Self> {
+        object.number().ok()
+    }
+}
+
+impl FromObject for i64 {
+    const TYPE_NAME: &'static str = "integer";
+
+    fn from_object(object: &Object) -> Option<Self> {
+        object.integer().ok()
+    }
+}
+
+impl FromObject for bool {
+    const TYPE_NAME: &'static str = "boolean";
+
+    fn from_object(object: &Object) -> Option<Self> {
+        match object {
+            Object::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromObject for String {
+    const TYPE_NAME: &'static str = "string";
+
+    fn from_object(object: &Object) -> Option<Self> {
+        match object {
+            Object::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
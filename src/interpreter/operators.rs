@@ -0,0 +1,94 @@
+use std::cmp::Ordering;
+
+use crate::error::{Error, Result};
+use crate::lexer::token::TokenKind;
+
+use super::object::Object;
+
+pub fn unary(operator: TokenKind, right: &Object) -> Result<Object> {
+    match operator {
+        TokenKind::Minus => right.number().map(|value| Object::Number(-value)),
+        TokenKind::Bang => Ok(Object::Boolean(!right.boolean())),
+        TokenKind::Tilde => right.integer().map(|value| Object::Number(!value as f32)),
+        _ => panic!("Unexpected unary operator {:?}", operator),
+    }
+}
+
+fn addition(left: &Object, right: &Object) -> Result<Object> {
+    match (left, right) {
+        (Object::String(rhs), Object::String(lhs)) => {
+            let mut result = rhs.to_owned();
+            result.push_str(lhs);
+            Ok(Object::String(result))
+        }
+        (Object::Number(rhs), Object::Number(lhs)) => Ok(Object::Number(rhs + lhs)),
+        (Object::String(_), _) | (_, Object::String(_)) => {
+            Ok(Object::String(left.str() + &right.str()))
+        }
+        _ => Err(Error::runtime(
+            "Operator + operands must be strings or numbers",
+        )),
+    }
+}
+
+pub fn binary(left: &Object, operator: TokenKind, right: &Object) -> Result<Object> {
+    if matches!(
+        operator,
+        TokenKind::Slash | TokenKind::SlashSlash | TokenKind::Percent
+    ) && right.number()? == 0.0
+    {
+        return Err(Error::runtime("Division by zero"));
+    }
+
+    let result = match operator {
+        TokenKind::Plus => return addition(left, right),
+        TokenKind::Greater => Object::Boolean(left.compare(right)? == Some(Ordering::Greater)),
+        TokenKind::GreaterEqual => Object::Boolean(matches!(
+            left.compare(right)?,
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        TokenKind::Less => Object::Boolean(left.compare(right)? == Some(Ordering::Less)),
+        TokenKind::LessEqual => Object::Boolean(matches!(
+            left.compare(right)?,
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        TokenKind::BangEqual => Object::Boolean(!left.equals(right)),
+        TokenKind::EqualEqual => Object::Boolean(left.equals(right)),
+        TokenKind::Minus => Object::Number(left.number()? - right.number()?),
+        TokenKind::Slash => Object::Number(left.number()? / right.number()?),
+        TokenKind::Star => Object::Number(left.number()? * right.number()?),
+        TokenKind::StarStar => Object::Number(left.number()?.powf(right.number()?)),
+        TokenKind::SlashSlash => Object::Number((left.number()? / right.number()?).floor()),
+        TokenKind::Percent => {
+            let (left, right) = (left.number()?, right.number()?);
+            Object::Number(left - right * (left / right).floor())
+        }
+        TokenKind::Ampersand => Object::Number((left.integer()? & right.integer()?) as f32),
+        TokenKind::Pipe => Object::Number((left.integer()? | right.integer()?) as f32),
+        TokenKind::Caret => Object::Number((left.integer()? ^ right.integer()?) as f32),
+        TokenKind::LessLess | TokenKind::GreaterGreater => return shift(left, operator, right),
+        _ => panic!("Unexpected binary operator {:?}", operator),
+    };
+
+    Ok(result)
+}
+
+fn shift(left: &Object, operator: TokenKind, right: &Object) -> Result<Object> {
+    let value = left.integer()?;
+    let amount = right.integer()?;
+
+    if !(0..64).contains(&amount) {
+        return Err(Error::runtime(format!(
+            "Shift amount {} is out of range",
+            amount
+        )));
+    }
+
+    let result = if operator == TokenKind::LessLess {
+        value << amount
+    } else {
+        value >> amount
+    };
+
+    Ok(Object::Number(result as f32))
+}
@@ -0,0 +1,350 @@
+use std::fmt::{Display, Write};
+
+use crate::cursor::{Cursor, ToCursor};
+use crate::error::{Error, Result};
+use crate::lexer::token::Span;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object<const N: usize>(members: [(&str, Value); N]) -> Self {
+        Value::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map_or(&Value::Null, |(_, value)| value),
+            _ => &Value::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Value::Number(value) if *value >= 0.0 => Some(*value as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Value] {
+        match self {
+            Value::Array(values) => values,
+            _ => &[],
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Number(value as f64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(values: Vec<Value>) -> Self {
+        Value::Array(values)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Number(value) => write!(f, "{}", value),
+            Value::String(value) => write_string(f, value),
+            Value::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(members) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for character in value.chars() {
+        match character {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            character if (character as u32) < 0x20 => write!(f, "\\u{:04x}", character as u32)?,
+            character => f.write_char(character)?,
+        }
+    }
+    f.write_char('"')
+}
+
+struct Parser {
+    cursor: Cursor<char>,
+}
+
+impl Parser {
+    fn value(&mut self) -> Result<Value> {
+        self.cursor.skip_while(|ch| ch.is_whitespace());
+
+        let value = match self.cursor.next_or_end() {
+            'n' => self.keyword("ull", Value::Null)?,
+            't' => self.keyword("rue", Value::Boolean(true))?,
+            'f' => self.keyword("alse", Value::Boolean(false))?,
+            '"' => Value::String(self.string()?),
+            '[' => self.array()?,
+            '{' => self.object()?,
+            character if character == '-' || character.is_ascii_digit() => {
+                self.number(character)?
+            }
+            _ => return Err(self.error("Expected a JSON value")),
+        };
+
+        self.cursor.skip_while(|ch| ch.is_whitespace());
+        Ok(value)
+    }
+
+    fn keyword(&mut self, rest: &str, value: Value) -> Result<Value> {
+        for expected in rest.chars() {
+            if !self.cursor.matches(expected) {
+                return Err(self.error("Unknown JSON keyword"));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn number(&mut self, first: char) -> Result<Value> {
+        let mut text = first.to_string();
+        while matches!(
+            self.cursor.peek_first(),
+            '0'..='9' | '.' | 'e' | 'E' | '+' | '-'
+        ) {
+            text.push(self.cursor.next_or_end());
+        }
+
+        text.parse::<f64>()
+            .map(Value::Number)
+            .map_err(|_| self.error("Invalid JSON number"))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let mut value = String::new();
+
+        loop {
+            if self.cursor.is_at_end() {
+                return Err(self.error("Unterminated JSON string"));
+            }
+
+            match self.cursor.next_or_end() {
+                '"' => break Ok(value),
+                '\\' => match self.cursor.next_or_end() {
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'u' => value.push(self.unicode_escape()?),
+                    character => value.push(character),
+                },
+                character => value.push(character),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char> {
+        let high = self.hex()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid JSON escape"));
+        }
+
+        if !(self.cursor.matches('\\') && self.cursor.matches('u')) {
+            return Err(self.error("Expected a low surrogate"));
+        }
+
+        let low = self.hex()?;
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff))
+            .ok_or_else(|| self.error("Invalid JSON escape"))
+    }
+
+    fn hex(&mut self) -> Result<u32> {
+        let digits = (0..4)
+            .map(|_| self.cursor.next_or_end())
+            .collect::<String>();
+
+        u32::from_str_radix(&digits, 16).map_err(|_| self.error("Invalid JSON escape"))
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        let mut values = Vec::new();
+
+        self.cursor.skip_while(|ch| ch.is_whitespace());
+        if self.cursor.matches(']') {
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            match self.cursor.next_or_end() {
+                ',' => continue,
+                ']' => break Ok(Value::Array(values)),
+                _ => break Err(self.error("Expected , or ] in JSON array")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value> {
+        let mut members = Vec::new();
+
+        self.cursor.skip_while(|ch| ch.is_whitespace());
+        if self.cursor.matches('}') {
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.cursor.skip_while(|ch| ch.is_whitespace());
+            if !self.cursor.matches('"') {
+                return Err(self.error("Expected a JSON object key"));
+            }
+
+            let key = self.string()?;
+
+            self.cursor.skip_while(|ch| ch.is_whitespace());
+            if !self.cursor.matches(':') {
+                return Err(self.error("Expected : in JSON object"));
+            }
+
+            members.push((key, self.value()?));
+
+            match self.cursor.next_or_end() {
+                ',' => continue,
+                '}' => break Ok(Value::Object(members)),
+                _ => break Err(self.error("Expected , or } in JSON object")),
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let offset = self.cursor.offset();
+        Error::syntax(
+            message,
+            Span {
+                offset,
+                end: offset,
+                ..Span::default()
+            },
+        )
+    }
+}
+
+pub fn parse(source: &str) -> Result<Value> {
+    let mut parser = Parser {
+        cursor: source.chars().collect::<Vec<char>>().to_cursor('\0'),
+    };
+
+    let value = parser.value()?;
+    if !parser.cursor.is_at_end() {
+        return Err(parser.error("Unexpected trailing JSON content"));
+    }
+
+    Ok(value)
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::{parse, Value};
+
+    #[test]
+    fn should_parse_json_values() {
+        let value =
+            parse(r#" {"id": 1, "params": {"text": "a\n\"b\" é", "list": [true, null, -2.5e1]}} "#)
+                .unwrap();
+
+        assert_eq!(value.get("id").as_usize(), Some(1));
+        assert_eq!(value.get("params").get("text").as_str(), Some("a\n\"b\" é"));
+        assert_eq!(
+            value.get("params").get("list").as_array(),
+            &[Value::Boolean(true), Value::Null, Value::Number(-25.0)]
+        );
+        assert_eq!(value.get("missing"), &Value::Null);
+    }
+
+    #[test]
+    fn should_reject_malformed_json() {
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("[1, 2").is_err());
+        assert!(parse("\"open").is_err());
+        assert!(parse("1 2").is_err());
+    }
+
+    #[test]
+    fn should_serialize_json_values() {
+        let value = Value::object([
+            ("name", Value::from("a \"quoted\"\nline")),
+            ("count", Value::from(2usize)),
+            ("items", Value::from(vec![Value::Null, Value::from(false)])),
+        ]);
+
+        assert_eq!(
+            value.to_string(),
+            r#"{"name":"a \"quoted\"\nline","count":2,"items":[null,false]}"#
+        );
+    }
+}
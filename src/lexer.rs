@@ -1,29 +1,66 @@
 use crate::cursor::{Cursor, ToCursor};
+use crate::error::{Error, Result};
+use crate::interner::Symbol;
+use crate::source::SourceId;
 
-use self::token::{Identifier, Token, TokenKind, TokenRepresentation};
+use self::token::{Identifier, Span, Token, TokenKind, TokenRepresentation};
 
 pub mod token;
 
 struct Lexer<'a> {
     source: &'a str,
+    source_id: SourceId,
     cursor: Cursor<char>,
+    /// The byte offset of every character, followed by the source length, as
+    /// the cursor counts characters while spans index the source by bytes.
+    offsets: Vec<usize>,
+    interpolations: Vec<usize>,
+    trivia: bool,
+    intern: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Lexer<'a> {
         Lexer {
             cursor: source.chars().collect::<Vec<char>>().to_cursor('\0'),
+            offsets: source
+                .char_indices()
+                .map(|(offset, _)| offset)
+                .chain([source.len()])
+                .collect(),
             source,
+            source_id: SourceId::UNKNOWN,
+            interpolations: Vec::new(),
+            trivia: false,
+            intern: false,
         }
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    pub fn with_trivia(source: &'a str) -> Lexer<'a> {
+        Lexer {
+            trivia: true,
+            ..Lexer::new(source)
+        }
+    }
+
+    pub fn tokenize(&mut self) -> Result<Vec<Token>> {
         let mut tokens = Vec::new();
 
         while !self.cursor.is_at_end() {
+            let whitespace_start_offset = self.offset();
             self.cursor.skip_while(|ch| ch.is_whitespace());
 
-            let token_start_offset = self.cursor.offset();
+            if self.trivia && self.offset() > whitespace_start_offset {
+                tokens.push(Token {
+                    kind: TokenKind::Whitespace,
+                    offset: whitespace_start_offset,
+                    end: self.offset(),
+                    symbol: None,
+                    source: self.source_id,
+                });
+            }
+
+            let token_start_offset = self.offset();
             let token_start_char = self.cursor.next_or_end();
 
             let token_kind = if token_start_char.is_numeric() {
@@ -31,25 +68,49 @@ impl<'a> Lexer<'a> {
             } else if token_start_char.is_identifier_start() {
                 self.parse_identifier(token_start_offset)
             } else if token_start_char == '\"' {
-                self.parse_string()
+                self.parse_string(
+                    token_start_offset,
+                    TokenKind::String,
+                    TokenKind::InterpolationStart,
+                )?
+            } else if token_start_char == '}' && self.interpolations.last() == Some(&0) {
+                self.interpolations.pop();
+                self.parse_string(
+                    token_start_offset,
+                    TokenKind::InterpolationEnd,
+                    TokenKind::InterpolationMiddle,
+                )?
+            } else if token_start_char == '#' {
+                self.parse_comment()
             } else {
                 self.parse_character(token_start_char)
             };
 
-            if token_kind == TokenKind::None {
+            if token_kind == TokenKind::None || (token_kind == TokenKind::Comment && !self.trivia) {
                 continue;
             }
 
+            let end = self.offset();
+            let symbol = (self.intern && token_kind == TokenKind::Identifier)
+                .then(|| Symbol::intern(&self.source[token_start_offset..end]));
+
             let token = Token {
                 kind: token_kind,
                 offset: token_start_offset,
-                end: self.cursor.offset(),
+                end,
+                symbol,
+                source: self.source_id,
             };
 
             tokens.push(token)
         }
 
-        tokens
+        if !self.interpolations.is_empty() {
+            let span = self.span_from(self.offset());
+            return Err(Error::syntax("Unterminated string interpolation", span));
+        }
+
+        Ok(tokens)
     }
 
     fn parse_character(&mut self, character: char) -> TokenKind {
@@ -61,15 +122,62 @@ impl<'a> Lexer<'a> {
             TokenKind::GreaterEqual
         } else if character == '=' && self.cursor.matches('=') {
             TokenKind::EqualEqual
+        } else if character == '<' && self.cursor.matches('<') {
+            self.parse_compound(TokenKind::LessLess, TokenKind::LessLessEqual)
+        } else if character == '>' && self.cursor.matches('>') {
+            self.parse_compound(TokenKind::GreaterGreater, TokenKind::GreaterGreaterEqual)
+        } else if character == '*' && self.cursor.matches('*') {
+            self.parse_compound(TokenKind::StarStar, TokenKind::StarStarEqual)
+        } else if character == '/' && self.cursor.matches('/') {
+            self.parse_compound(TokenKind::SlashSlash, TokenKind::SlashSlashEqual)
+        } else if character == '?' && self.cursor.matches('?') {
+            TokenKind::QuestionQuestion
+        } else if character == '?' && self.cursor.matches('.') {
+            TokenKind::QuestionDot
+        } else if character == '+' && self.cursor.matches('+') {
+            TokenKind::PlusPlus
+        } else if character == '-' && self.cursor.matches('-') {
+            TokenKind::MinusMinus
+        } else if character == '{' || character == '}' {
+            self.parse_brace(character)
         } else {
-            character.get_token_kind()
+            let kind = character.get_token_kind();
+            match kind.compound_assignment() {
+                Some(compound) if self.cursor.matches('=') => compound,
+                _ => kind,
+            }
+        }
+    }
+
+    fn parse_comment(&mut self) -> TokenKind {
+        self.cursor.skip_while(|ch| ch != '\n');
+        TokenKind::Comment
+    }
+
+    fn parse_brace(&mut self, character: char) -> TokenKind {
+        if let Some(depth) = self.interpolations.last_mut() {
+            if character == '{' {
+                *depth += 1;
+            } else {
+                *depth -= 1;
+            }
+        }
+
+        character.get_token_kind()
+    }
+
+    fn parse_compound(&mut self, kind: TokenKind, compound: TokenKind) -> TokenKind {
+        if self.cursor.matches('=') {
+            compound
+        } else {
+            kind
         }
     }
 
     fn parse_identifier(&mut self, token_start_offset: usize) -> TokenKind {
         self.cursor.skip_while(|ch| ch.is_identifier_continue());
 
-        let token_value = &self.source[token_start_offset..self.cursor.offset()];
+        let token_value = &self.source[token_start_offset..self.offset()];
         match token_value.get_token_kind() {
             TokenKind::None => TokenKind::Identifier,
             other => other,
@@ -86,35 +194,81 @@ impl<'a> Lexer<'a> {
         TokenKind::Number
     }
 
-    fn parse_string(&mut self) -> TokenKind {
-        self.cursor.skip_while(|ch| ch != '"');
-        if self.cursor.peek(0) != '"' {
-            panic!("Unterminated string")
-        } else {
-            self.cursor.next_or_end();
+    fn parse_string(
+        &mut self,
+        token_start_offset: usize,
+        plain: TokenKind,
+        interpolated: TokenKind,
+    ) -> Result<TokenKind> {
+        while !self.cursor.is_at_end() {
+            let character = self.cursor.next_or_end();
+
+            if character == '"' {
+                return Ok(plain);
+            } else if character == '$' && self.cursor.matches('{') {
+                self.interpolations.push(0);
+                return Ok(interpolated);
+            }
         }
 
-        TokenKind::String
+        let span = self.span_from(token_start_offset);
+        Err(Error::syntax("Unterminated string", span))
+    }
+
+    fn offset(&self) -> usize {
+        self.offsets[self.cursor.offset()]
+    }
+
+    fn span_from(&self, offset: usize) -> Span {
+        Span {
+            offset,
+            end: self.offset(),
+            source: self.source_id,
+        }
     }
 }
 
-pub fn tokenize(source: &str) -> Vec<Token> {
+pub fn tokenize(source: &str) -> Result<Vec<Token>> {
     let mut lexer = Lexer::new(source);
     lexer.tokenize()
 }
 
+/// Like `tokenize`, but marks every token as coming from a registered source
+/// and interns identifiers for the interpreter.
+pub fn tokenize_source(source: &str, id: SourceId) -> Result<Vec<Token>> {
+    let mut lexer = Lexer {
+        source_id: id,
+        intern: true,
+        ..Lexer::new(source)
+    };
+    lexer.tokenize()
+}
+
+/// Like `tokenize`, but keeps whitespace and comments as tokens so the source
+/// can be reproduced exactly.
+pub fn tokenize_with_trivia(source: &str) -> Result<Vec<Token>> {
+    let mut lexer = Lexer::with_trivia(source);
+    lexer.tokenize()
+}
+
 mod tests {
 
     #[allow(unused_imports)]
     use crate::lexer::token::TokenKind;
 
     #[allow(unused_imports)]
-    use super::tokenize;
+    use super::{tokenize, tokenize_source, tokenize_with_trivia};
+
+    #[allow(unused_imports)]
+    use crate::interner::Symbol;
+
+    #[allow(unused_imports)]
+    use crate::source::SourceId;
 
     #[test]
     fn should_parse_text1() {
         let source = "   23123123 some_identifier         \"some string\"";
-        let tokens = tokenize(source);
+        let tokens = tokenize(source).unwrap();
 
         assert_eq!(tokens.len(), 3);
         assert_eq!(tokens[0].kind, TokenKind::Number);
@@ -124,8 +278,8 @@ mod tests {
 
     #[test]
     fn should_parse_text2() {
-        let source = "{}[]();+-===<=>=!==></*";
-        let tokens = tokenize(source);
+        let source = "{}[]();+- ===<=>=!==></*";
+        let tokens = tokenize(source).unwrap();
 
         assert_eq!(tokens.len(), 19);
 
@@ -153,11 +307,146 @@ mod tests {
     #[test]
     fn should_parse_text3() {
         let source = "123.3123.function_call";
-        let tokens = tokenize(source);
+        let tokens = tokenize(source).unwrap();
 
         assert_eq!(tokens.len(), 3);
         assert_eq!(tokens[0].kind, TokenKind::Number);
         assert_eq!(tokens[1].kind, TokenKind::Dot);
         assert_eq!(tokens[2].kind, TokenKind::Identifier);
     }
+
+    #[test]
+    fn should_parse_text4() {
+        let source = "%**//&|^~<<>>";
+        let tokens = tokenize(source).unwrap();
+
+        assert_eq!(tokens.len(), 9);
+
+        assert_eq!(tokens[0].kind, TokenKind::Percent);
+        assert_eq!(tokens[1].kind, TokenKind::StarStar);
+        assert_eq!(tokens[2].kind, TokenKind::SlashSlash);
+        assert_eq!(tokens[3].kind, TokenKind::Ampersand);
+        assert_eq!(tokens[4].kind, TokenKind::Pipe);
+        assert_eq!(tokens[5].kind, TokenKind::Caret);
+        assert_eq!(tokens[6].kind, TokenKind::Tilde);
+        assert_eq!(tokens[7].kind, TokenKind::LessLess);
+        assert_eq!(tokens[8].kind, TokenKind::GreaterGreater);
+    }
+
+    #[test]
+    fn should_parse_text5() {
+        let source = "+= -= *= /= %= **= //= &= |= ^= <<= >>= ++ --";
+        let tokens = tokenize(source).unwrap();
+
+        assert_eq!(tokens.len(), 14);
+
+        assert_eq!(tokens[0].kind, TokenKind::PlusEqual);
+        assert_eq!(tokens[1].kind, TokenKind::MinusEqual);
+        assert_eq!(tokens[2].kind, TokenKind::StarEqual);
+        assert_eq!(tokens[3].kind, TokenKind::SlashEqual);
+        assert_eq!(tokens[4].kind, TokenKind::PercentEqual);
+        assert_eq!(tokens[5].kind, TokenKind::StarStarEqual);
+        assert_eq!(tokens[6].kind, TokenKind::SlashSlashEqual);
+        assert_eq!(tokens[7].kind, TokenKind::AmpersandEqual);
+        assert_eq!(tokens[8].kind, TokenKind::PipeEqual);
+        assert_eq!(tokens[9].kind, TokenKind::CaretEqual);
+        assert_eq!(tokens[10].kind, TokenKind::LessLessEqual);
+        assert_eq!(tokens[11].kind, TokenKind::GreaterGreaterEqual);
+        assert_eq!(tokens[12].kind, TokenKind::PlusPlus);
+        assert_eq!(tokens[13].kind, TokenKind::MinusMinus);
+    }
+
+    #[test]
+    fn should_parse_text6() {
+        let source = "\"a ${b + {}} c ${\"d\"} e\"";
+        let tokens = tokenize(source).unwrap();
+
+        assert_eq!(tokens.len(), 8);
+
+        assert_eq!(tokens[0].kind, TokenKind::InterpolationStart);
+        assert_eq!(tokens[1].kind, TokenKind::Identifier);
+        assert_eq!(tokens[2].kind, TokenKind::Plus);
+        assert_eq!(tokens[3].kind, TokenKind::LeftBrace);
+        assert_eq!(tokens[4].kind, TokenKind::RightBrace);
+        assert_eq!(tokens[5].kind, TokenKind::InterpolationMiddle);
+        assert_eq!(tokens[6].kind, TokenKind::String);
+        assert_eq!(tokens[7].kind, TokenKind::InterpolationEnd);
+        assert_eq!(tokens[7].end, source.len());
+    }
+
+    #[test]
+    fn should_parse_text7() {
+        let source = "nil ?? nil_value?.name";
+        let tokens = tokenize(source).unwrap();
+
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0].kind, TokenKind::Nil);
+        assert_eq!(tokens[1].kind, TokenKind::QuestionQuestion);
+        assert_eq!(tokens[2].kind, TokenKind::Identifier);
+        assert_eq!(tokens[3].kind, TokenKind::QuestionDot);
+        assert_eq!(tokens[4].kind, TokenKind::Identifier);
+    }
+
+    #[test]
+    fn should_report_unterminated_string() {
+        let source = "1 + \"abc";
+        let error = tokenize(source).unwrap_err();
+
+        assert_eq!(error.message, "Unterminated string");
+        assert_eq!(error.span.map(|span| span.offset), Some(4));
+    }
+
+    #[test]
+    fn should_skip_or_keep_comments_and_whitespace() {
+        let source = "a # note\n  b";
+
+        let kinds = tokenize(source)
+            .unwrap()
+            .iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![TokenKind::Identifier, TokenKind::Identifier]);
+
+        let tokens = tokenize_with_trivia(source).unwrap();
+        let kinds = tokens.iter().map(|token| token.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Identifier,
+                TokenKind::Whitespace,
+                TokenKind::Comment,
+                TokenKind::Whitespace,
+                TokenKind::Identifier,
+            ]
+        );
+        assert_eq!(&source[tokens[2].offset..tokens[2].end], "# note");
+        assert_eq!(&source[tokens[3].offset..tokens[3].end], "\n  ");
+    }
+
+    #[test]
+    fn should_use_byte_offsets_after_non_ascii_text() {
+        let source = "# café\nvar abc = \"né\";";
+        let tokens = tokenize_with_trivia(source).unwrap();
+
+        let values = tokens
+            .iter()
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| &source[token.offset..token.end])
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec!["# café", "var", "abc", "=", "\"né\"", ";"]);
+
+        let error = tokenize("\"é").unwrap_err();
+        assert_eq!(error.span.map(|span| (span.offset, span.end)), Some((0, 3)));
+    }
+
+    #[test]
+    fn should_only_intern_identifiers_of_source_that_runs() {
+        let tokens = tokenize("lexed_only_identifier").unwrap();
+        assert_eq!(tokens[0].symbol, None);
+        assert_eq!(Symbol::lookup("lexed_only_identifier"), None);
+
+        let tokens = tokenize_source("run_identifier", SourceId::UNKNOWN).unwrap();
+        assert_eq!(tokens[0].symbol, Symbol::lookup("run_identifier"));
+        assert!(tokens[0].symbol.is_some());
+    }
 }
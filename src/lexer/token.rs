@@ -1,3 +1,6 @@
+use crate::interner::Symbol;
+use crate::source::SourceId;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 
 pub enum TokenKind {
@@ -9,10 +12,16 @@ pub enum TokenKind {
     RightBracket,
     Comma,
     Dot,
+    Colon,
     Minus,
     Plus,
     Slash,
     Star,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
     Equal,
     Less,
     Greater,
@@ -22,10 +31,35 @@ pub enum TokenKind {
     GreaterEqual,
     EqualEqual,
     BangEqual,
+    StarStar,
+    SlashSlash,
+    LessLess,
+    GreaterGreater,
+    QuestionQuestion,
+    QuestionDot,
+    PlusPlus,
+    MinusMinus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    StarStarEqual,
+    SlashSlashEqual,
+    AmpersandEqual,
+    PipeEqual,
+    CaretEqual,
+    LessLessEqual,
+    GreaterGreaterEqual,
 
     Identifier,
     String,
     Number,
+    InterpolationStart,
+    InterpolationMiddle,
+    InterpolationEnd,
+    Whitespace,
+    Comment,
 
     And,
     Else,
@@ -34,21 +68,83 @@ pub enum TokenKind {
     Var,
     True,
     False,
+    Nil,
     Class,
     Fn,
     For,
     While,
     Return,
     Print,
+    Import,
+    Export,
+    As,
+    Try,
+    Catch,
+    Finally,
+    Throw,
 
     None,
 }
 
+impl TokenKind {
+    pub fn compound_assignment(self) -> Option<TokenKind> {
+        match self {
+            TokenKind::Plus => Some(TokenKind::PlusEqual),
+            TokenKind::Minus => Some(TokenKind::MinusEqual),
+            TokenKind::Star => Some(TokenKind::StarEqual),
+            TokenKind::Slash => Some(TokenKind::SlashEqual),
+            TokenKind::Percent => Some(TokenKind::PercentEqual),
+            TokenKind::Ampersand => Some(TokenKind::AmpersandEqual),
+            TokenKind::Pipe => Some(TokenKind::PipeEqual),
+            TokenKind::Caret => Some(TokenKind::CaretEqual),
+            _ => None,
+        }
+    }
+
+    pub fn compound_operator(self) -> Option<TokenKind> {
+        match self {
+            TokenKind::PlusEqual => Some(TokenKind::Plus),
+            TokenKind::MinusEqual => Some(TokenKind::Minus),
+            TokenKind::StarEqual => Some(TokenKind::Star),
+            TokenKind::SlashEqual => Some(TokenKind::Slash),
+            TokenKind::PercentEqual => Some(TokenKind::Percent),
+            TokenKind::StarStarEqual => Some(TokenKind::StarStar),
+            TokenKind::SlashSlashEqual => Some(TokenKind::SlashSlash),
+            TokenKind::AmpersandEqual => Some(TokenKind::Ampersand),
+            TokenKind::PipeEqual => Some(TokenKind::Pipe),
+            TokenKind::CaretEqual => Some(TokenKind::Caret),
+            TokenKind::LessLessEqual => Some(TokenKind::LessLess),
+            TokenKind::GreaterGreaterEqual => Some(TokenKind::GreaterGreater),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Token {
     pub kind: TokenKind,
     pub offset: usize,
     pub end: usize,
+    /// The interned name of an identifier, set by `lexer::tokenize_source`.
+    pub symbol: Option<Symbol>,
+    pub source: SourceId,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Span {
+    pub offset: usize,
+    pub end: usize,
+    pub source: SourceId,
+}
+
+impl Span {
+    pub fn join(self, other: Span) -> Span {
+        Span {
+            offset: self.offset.min(other.offset),
+            end: self.end.max(other.end),
+            source: self.source,
+        }
+    }
 }
 
 impl Token {
@@ -57,6 +153,16 @@ impl Token {
             kind,
             offset: 0,
             end: 0,
+            symbol: None,
+            source: SourceId::UNKNOWN,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            offset: self.offset,
+            end: self.end,
+            source: self.source,
         }
     }
 }
@@ -82,6 +188,7 @@ impl TokenRepresentation for char {
             '}' => TokenKind::RightBrace,
             '.' => TokenKind::Dot,
             ',' => TokenKind::Comma,
+            ':' => TokenKind::Colon,
             '-' => TokenKind::Minus,
             '+' => TokenKind::Plus,
             '=' => TokenKind::Equal,
@@ -89,6 +196,11 @@ impl TokenRepresentation for char {
             '>' => TokenKind::Greater,
             '*' => TokenKind::Star,
             '/' => TokenKind::Slash,
+            '%' => TokenKind::Percent,
+            '&' => TokenKind::Ampersand,
+            '|' => TokenKind::Pipe,
+            '^' => TokenKind::Caret,
+            '~' => TokenKind::Tilde,
             '!' => TokenKind::Bang,
             ';' => TokenKind::Semicolon,
             _ => TokenKind::None,
@@ -96,6 +208,11 @@ impl TokenRepresentation for char {
     }
 }
 
+pub const KEYWORDS: [&str; 21] = [
+    "and", "as", "catch", "class", "else", "export", "false", "finally", "fn", "for", "if",
+    "import", "nil", "or", "print", "return", "throw", "true", "try", "var", "while",
+];
+
 impl TokenRepresentation for str {
     fn get_token_kind(&self) -> TokenKind {
         match self {
@@ -106,12 +223,20 @@ impl TokenRepresentation for str {
             "or" => TokenKind::Or,
             "true" => TokenKind::True,
             "false" => TokenKind::False,
+            "nil" => TokenKind::Nil,
             "class" => TokenKind::Class,
             "fn" => TokenKind::Fn,
             "for" => TokenKind::For,
             "while" => TokenKind::While,
             "return" => TokenKind::Return,
             "print" => TokenKind::Print,
+            "import" => TokenKind::Import,
+            "export" => TokenKind::Export,
+            "as" => TokenKind::As,
+            "try" => TokenKind::Try,
+            "catch" => TokenKind::Catch,
+            "finally" => TokenKind::Finally,
+            "throw" => TokenKind::Throw,
             _ => TokenKind::None,
         }
     }
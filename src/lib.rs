@@ -0,0 +1,25 @@
+pub mod checker;
+pub mod cursor;
+pub mod debugger;
+pub mod editor;
+pub mod error;
+pub mod formatter;
+pub mod interner;
+pub mod interpreter;
+pub mod json;
+pub mod lexer;
+pub mod linter;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod profiler;
+pub mod source;
+
+pub use checker::types::Type;
+pub use error::{Error, ErrorKind, Result};
+pub use interner::Symbol;
+pub use interpreter::io::{SharedBuffer, Streams};
+pub use interpreter::limits::Limits;
+pub use interpreter::native::Arguments;
+pub use interpreter::object::{FromObject, Object};
+pub use interpreter::ProgramContext;
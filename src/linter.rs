@@ -0,0 +1,467 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::error::Result;
+use crate::lexer::token::{Span, Token, TokenKind};
+use crate::parser::tree::Assignment;
+use crate::parser::tree::Binary;
+use crate::parser::tree::Call;
+use crate::parser::tree::Constant;
+use crate::parser::tree::ExpressionNode;
+use crate::parser::tree::Get;
+use crate::parser::tree::Import;
+use crate::parser::tree::Literal;
+use crate::parser::tree::Statement;
+use crate::parser::tree::StatementNode;
+use crate::parser::tree::StatementVisitor;
+use crate::parser::tree::Try;
+use crate::parser::tree::Unary;
+use crate::parser::tree::Update;
+use crate::parser::tree::VariableDefinition;
+use crate::source::LineIndex;
+use crate::{lexer, parser};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Lint {
+    UnusedVariable,
+    Redeclaration,
+    SelfComparison,
+    ConstantCondition,
+    NoEffect,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedVariable,
+        Lint::Redeclaration,
+        Lint::SelfComparison,
+        Lint::ConstantCondition,
+        Lint::NoEffect,
+    ];
+
+    /// The stable code used in reports and `# lint: allow(...)` comments.
+    pub fn code(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "L001",
+            Lint::Redeclaration => "L002",
+            Lint::SelfComparison => "L003",
+            Lint::ConstantCondition => "L004",
+            Lint::NoEffect => "L005",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::Redeclaration => "redeclaration",
+            Lint::SelfComparison => "self-comparison",
+            Lint::ConstantCondition => "constant-condition",
+            Lint::NoEffect => "no-effect",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Warning {
+    pub lint: Lint,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "warning[{} {}]: {}",
+            self.lint.code(),
+            self.lint.name(),
+            self.message
+        )
+    }
+}
+
+struct Declaration {
+    name: String,
+    span: Span,
+    used: bool,
+}
+
+#[derive(Default)]
+struct Scope {
+    declarations: Vec<Declaration>,
+    latest: HashMap<String, usize>,
+}
+
+impl Scope {
+    fn declare(&mut self, name: &str, span: Span, used: bool) {
+        self.latest
+            .insert(name.to_string(), self.declarations.len());
+        self.declarations.push(Declaration {
+            name: name.to_string(),
+            span,
+            used,
+        });
+    }
+
+    fn use_variable(&mut self, name: &str) {
+        if let Some(index) = self.latest.get(name) {
+            self.declarations[*index].used = true;
+        }
+    }
+}
+
+struct StatementLinter<'a> {
+    tree: &'a Statement,
+    source: &'a str,
+    scope: &'a mut Scope,
+    warnings: &'a mut Vec<Warning>,
+    exported: bool,
+}
+
+impl<'a> StatementLinter<'a> {
+    fn lint(&mut self) {
+        self.lint_statement(self.tree.root_index);
+    }
+
+    fn lint_statement(&mut self, index: usize) {
+        if matches!(self.tree.tree[index], StatementNode::Expression(_)) && !self.has_effect(index)
+        {
+            self.warn(
+                Lint::NoEffect,
+                "Expression statement has no effect".to_string(),
+                self.tree.spans[index],
+            );
+        }
+
+        self.visit(index);
+    }
+
+    fn visit(&mut self, index: usize) {
+        self.tree.tree.get(index).unwrap().visit(self)
+    }
+
+    fn warn(&mut self, lint: Lint, message: String, span: Span) {
+        self.warnings.push(Warning {
+            lint,
+            message,
+            span,
+        });
+    }
+
+    /// Whether evaluating the node can change state or produce output.
+    fn has_effect(&self, index: usize) -> bool {
+        let StatementNode::Expression(expression) = &self.tree.tree[index] else {
+            return true;
+        };
+
+        match expression {
+            ExpressionNode::Assignment(_) | ExpressionNode::Update(_) | ExpressionNode::Call(_) => {
+                true
+            }
+            ExpressionNode::Unary(unary) => self.has_effect(unary.right),
+            ExpressionNode::Binary(binary) => {
+                self.has_effect(binary.left) || self.has_effect(binary.right)
+            }
+            ExpressionNode::Grouping(inner) => self.has_effect(*inner),
+            ExpressionNode::Interpolation(parts) => parts.iter().any(|part| self.has_effect(*part)),
+            ExpressionNode::Get(get) => self.has_effect(get.object),
+            ExpressionNode::Literal(_)
+            | ExpressionNode::Variable(_)
+            | ExpressionNode::Constant(_) => false,
+        }
+    }
+
+    /// Whether the node evaluates to the same value every time it runs.
+    fn is_constant(&self, index: usize) -> bool {
+        let StatementNode::Expression(expression) = &self.tree.tree[index] else {
+            return false;
+        };
+
+        match expression {
+            ExpressionNode::Literal(_) | ExpressionNode::Constant(_) => true,
+            ExpressionNode::Unary(unary) => self.is_constant(unary.right),
+            ExpressionNode::Binary(binary) => {
+                self.is_constant(binary.left) && self.is_constant(binary.right)
+            }
+            ExpressionNode::Grouping(inner) => self.is_constant(*inner),
+            ExpressionNode::Interpolation(parts) => {
+                parts.iter().all(|part| self.is_constant(*part))
+            }
+            _ => false,
+        }
+    }
+
+    fn get_span_value(&self, index: usize) -> &'a str {
+        let span = self.tree.spans[index];
+        &self.source[span.offset..span.end]
+    }
+
+    fn get_token_value(&self, token: &Token) -> &'a str {
+        &self.source[token.offset..token.end]
+    }
+}
+
+impl StatementVisitor<()> for StatementLinter<'_> {
+    fn handle_literal_expression(&mut self, _literal: &Literal) {}
+
+    fn handle_binary_expression(&mut self, binary: &Binary) {
+        self.visit(binary.left);
+        self.visit(binary.right);
+
+        let comparison = matches!(
+            binary.operator.kind,
+            TokenKind::EqualEqual
+                | TokenKind::BangEqual
+                | TokenKind::Less
+                | TokenKind::LessEqual
+                | TokenKind::Greater
+                | TokenKind::GreaterEqual
+        );
+        let left = self.get_span_value(binary.left);
+        if comparison && !self.has_effect(binary.left) && left == self.get_span_value(binary.right)
+        {
+            self.warn(
+                Lint::SelfComparison,
+                format!("Comparing {} with itself", left),
+                binary.operator.span(),
+            );
+        }
+
+        if binary.operator.kind == TokenKind::QuestionQuestion && self.is_constant(binary.left) {
+            self.warn(
+                Lint::ConstantCondition,
+                format!("Left side of ?? is the constant {}", left),
+                self.tree.spans[binary.left],
+            );
+        }
+    }
+
+    fn handle_grouping_expression(&mut self, index: usize) {
+        self.visit(index);
+    }
+
+    fn handle_unary_expression(&mut self, unary: &Unary) {
+        self.visit(unary.right);
+    }
+
+    fn handle_variable_expression(&mut self, variable: &Token) {
+        let name = self.get_token_value(variable);
+        self.scope.use_variable(name);
+    }
+
+    fn handle_assignment_expression(&mut self, assignment: &Assignment) {
+        self.visit(assignment.value);
+
+        if assignment.operator.kind.compound_operator().is_some() {
+            let name = self.get_token_value(&assignment.target);
+            self.scope.use_variable(name);
+        }
+    }
+
+    fn handle_update_expression(&mut self, update: &Update) {
+        let name = self.get_token_value(&update.target);
+        self.scope.use_variable(name);
+    }
+
+    fn handle_interpolation_expression(&mut self, parts: &[usize]) {
+        for part in parts {
+            self.visit(*part);
+        }
+    }
+
+    fn handle_call_expression(&mut self, call: &Call) {
+        self.visit(call.callee);
+        for argument in call.arguments.iter() {
+            self.visit(*argument);
+        }
+    }
+
+    fn handle_get_expression(&mut self, get: &Get) {
+        self.visit(get.object);
+    }
+
+    fn handle_constant_expression(&mut self, _constant: &Constant) {}
+
+    fn handle_variable_definition_statement(&mut self, declaration: &VariableDefinition) {
+        self.visit(declaration.expression);
+
+        let name = self.get_token_value(&declaration.identifier);
+        let span = declaration.identifier.span();
+
+        if self.scope.latest.contains_key(name) {
+            self.warn(
+                Lint::Redeclaration,
+                format!("Variable {} is already declared in this scope", name),
+                span,
+            );
+        }
+
+        self.scope.declare(name, span, self.exported);
+    }
+
+    fn handle_print_statement(&mut self, expression: usize) {
+        self.visit(expression);
+    }
+
+    fn handle_import_statement(&mut self, import: &Import) {
+        let name = self.get_token_value(&import.alias);
+        self.scope.declare(name, import.alias.span(), false);
+    }
+
+    fn handle_export_statement(&mut self, declaration: usize) {
+        self.exported = true;
+        self.visit(declaration);
+    }
+
+    fn handle_block_statement(&mut self, statements: &[usize]) {
+        for statement in statements {
+            self.lint_statement(*statement);
+        }
+    }
+
+    fn handle_try_statement(&mut self, statement: &Try) {
+        self.visit(statement.body);
+
+        if let Some(handler) = &statement.handler {
+            self.visit(handler.body);
+        }
+
+        if let Some(finalizer) = statement.finalizer {
+            self.visit(finalizer);
+        }
+    }
+
+    fn handle_throw_statement(&mut self, expression: usize) {
+        self.visit(expression);
+    }
+}
+
+/// Collects the lints allowed by `# lint: allow(L001, no-effect)` comments,
+/// keyed by line. A comment covers its own line and the line below it.
+fn get_allowed_lints(source: &str, lines: &LineIndex) -> Result<HashMap<usize, HashSet<String>>> {
+    let mut allowed = HashMap::<usize, HashSet<String>>::new();
+
+    for token in lexer::tokenize_with_trivia(source)? {
+        if token.kind != TokenKind::Comment {
+            continue;
+        }
+
+        let comment = source[token.offset + 1..token.end].trim();
+        let Some(lints) = comment
+            .strip_prefix("lint: allow(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        else {
+            continue;
+        };
+
+        let line = lines.line(token.offset);
+        for lint in lints.split(',').map(str::trim) {
+            for covered in [line, line + 1] {
+                allowed.entry(covered).or_default().insert(lint.to_string());
+            }
+        }
+    }
+
+    Ok(allowed)
+}
+
+/// Reports likely mistakes in a program without running it, sorted by
+/// position. Syntax errors are returned as the error.
+pub fn lint(source: &str) -> Result<Vec<Warning>> {
+    let statements = parser::parse(lexer::tokenize(source)?)?;
+
+    let mut scope = Scope::default();
+    let mut warnings = Vec::new();
+
+    for statement in statements.iter() {
+        let mut linter = StatementLinter {
+            tree: statement,
+            source,
+            scope: &mut scope,
+            warnings: &mut warnings,
+            exported: false,
+        };
+        linter.lint();
+    }
+
+    for declaration in scope
+        .declarations
+        .iter()
+        .filter(|declaration| !declaration.used)
+    {
+        warnings.push(Warning {
+            lint: Lint::UnusedVariable,
+            message: format!("Variable {} is never used", declaration.name),
+            span: declaration.span,
+        });
+    }
+
+    let lines = LineIndex::new(source);
+    let allowed = get_allowed_lints(source, &lines)?;
+
+    warnings.retain(|warning| {
+        let line = lines.line(warning.span.offset);
+        allowed.get(&line).is_none_or(|lints| {
+            !lints.contains(warning.lint.code()) && !lints.contains(warning.lint.name())
+        })
+    });
+    warnings.sort_by_key(|warning| warning.span.offset);
+
+    Ok(warnings)
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::{lint, Lint};
+
+    #[allow(dead_code)]
+    fn codes(source: &str) -> Vec<(&'static str, String)> {
+        lint(source)
+            .unwrap()
+            .into_iter()
+            .map(|warning| {
+                let span = warning.span;
+                (
+                    warning.lint.code(),
+                    source[span.offset..span.end].to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_report_each_lint() {
+        let source =
+            "var a = 1;\nvar b = 2;\nvar a = b;\nprint a == a;\nprint nil ?? a;\n1 + a;\nstr(a);";
+
+        assert_eq!(
+            codes(source),
+            vec![
+                ("L001", "a".to_string()),
+                ("L002", "a".to_string()),
+                ("L003", "==".to_string()),
+                ("L004", "nil".to_string()),
+                ("L005", "1 + a".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_count_reads_and_exports_as_uses() {
+        let source = "export var a = 1;\nvar b = 0;\nb++;\nvar c = 0;\nc = 1;\nimport \"m\" as m;\nprint m.x;";
+
+        assert_eq!(codes(source), vec![("L001", "c".to_string())]);
+    }
+
+    #[test]
+    fn should_suppress_allowed_lints() {
+        let source = "# lint: allow(unused-variable)\nvar a = 1;\nvar b = 1; # lint: allow(L001)\n1; # lint: allow(L003)";
+
+        assert_eq!(codes(source), vec![("L005", "1".to_string())]);
+        assert_eq!(
+            Lint::ALL.map(Lint::code),
+            ["L001", "L002", "L003", "L004", "L005"]
+        );
+    }
+}
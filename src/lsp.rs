@@ -0,0 +1,698 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::error::{Error, Result};
+use crate::interpreter::object::Object;
+use crate::interpreter::ProgramContext;
+use crate::json::{self, Value};
+use crate::lexer::token::{Span, Token, TokenKind};
+use crate::source::LineIndex;
+use crate::{lexer, parser};
+
+const METHOD_NOT_FOUND: i32 = -32601;
+const PARSE_ERROR: i32 = -32700;
+
+const SEVERITY_ERROR: usize = 1;
+const SYMBOL_MODULE: usize = 2;
+const SYMBOL_VARIABLE: usize = 13;
+
+#[derive(Clone, Copy)]
+enum DeclarationKind {
+    Variable,
+    Import,
+    CatchParameter,
+}
+
+struct Declaration {
+    kind: DeclarationKind,
+    identifier: Token,
+    statement: Span,
+}
+
+impl Declaration {
+    /// Recognizes `var name`, `import "path" as name` and `catch (name)`
+    /// starting at the keyword found at `index`.
+    fn find(tokens: &[Token], index: usize) -> Option<Self> {
+        let kinds = |offset: usize| tokens.get(index + offset).map(|token| token.kind);
+        let (kind, identifier, end) = match tokens[index].kind {
+            TokenKind::Var => (DeclarationKind::Variable, 1, TokenKind::Semicolon),
+            TokenKind::Import
+                if kinds(1) == Some(TokenKind::String) && kinds(2) == Some(TokenKind::As) =>
+            {
+                (DeclarationKind::Import, 3, TokenKind::Semicolon)
+            }
+            TokenKind::Catch if kinds(1) == Some(TokenKind::LeftParen) => {
+                (DeclarationKind::CatchParameter, 2, TokenKind::RightParen)
+            }
+            _ => return None,
+        };
+
+        let identifier = tokens
+            .get(index + identifier)
+            .filter(|token| token.kind == TokenKind::Identifier)?;
+        let end = tokens[index..]
+            .iter()
+            .find(|token| token.kind == end)
+            .unwrap_or(identifier);
+
+        Some(Self {
+            kind,
+            identifier: *identifier,
+            statement: tokens[index].span().join(end.span()),
+        })
+    }
+}
+
+struct Document {
+    source: String,
+    lines: LineIndex,
+    tokens: Vec<Token>,
+    declarations: Vec<Declaration>,
+    error: Option<Error>,
+}
+
+impl Document {
+    fn new(source: String) -> Self {
+        let lines = LineIndex::new(&source);
+        let (tokens, error) = match lexer::tokenize(&source) {
+            Ok(tokens) => {
+                let error = parser::parse(tokens.clone()).err();
+                (tokens, error)
+            }
+            Err(error) => (Vec::new(), Some(error)),
+        };
+
+        let declarations = (0..tokens.len())
+            .filter_map(|index| Declaration::find(&tokens, index))
+            .collect();
+
+        Self {
+            source,
+            lines,
+            tokens,
+            declarations,
+            error,
+        }
+    }
+
+    fn name(&self, token: &Token) -> &str {
+        &self.source[token.offset..token.end]
+    }
+
+    /// Positions count UTF-16 code units from the start of a line, spans
+    /// count bytes from the start of the source.
+    fn offset(&self, position: &Value) -> Option<usize> {
+        let line = position.get("line").as_usize()?;
+        let character = position.get("character").as_usize()?;
+        let (start, end) = self.lines.line_range(line + 1)?;
+
+        let mut units = 0;
+        for (offset, ch) in self.source[start..end].char_indices() {
+            if units >= character {
+                return Some(start + offset);
+            }
+            units += ch.len_utf16();
+        }
+
+        Some(end)
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let line = self.lines.line(offset);
+        let (start, _) = self.lines.line_range(line).unwrap_or_default();
+        let character = self.source[start..offset].encode_utf16().count();
+
+        Value::object([
+            ("line", Value::from(line - 1)),
+            ("character", Value::from(character)),
+        ])
+    }
+
+    fn range(&self, span: Span) -> Value {
+        Value::object([
+            ("start", self.position(span.offset)),
+            ("end", self.position(span.end)),
+        ])
+    }
+
+    /// Property names after `.` or `?.` are not variables, so they are skipped.
+    fn identifier_at(&self, position: &Value) -> Option<Token> {
+        let offset = self.offset(position)?;
+        let index = self.tokens.iter().position(|token| {
+            token.kind == TokenKind::Identifier && token.offset <= offset && offset <= token.end
+        })?;
+
+        match index
+            .checked_sub(1)
+            .map(|previous| self.tokens[previous].kind)
+        {
+            Some(TokenKind::Dot | TokenKind::QuestionDot) => None,
+            _ => Some(self.tokens[index]),
+        }
+    }
+
+    /// Variables can be redeclared, so a use refers to the closest declaration
+    /// before it, or to the first one when used ahead of any declaration.
+    fn declaration_of(&self, token: &Token) -> Option<&Declaration> {
+        let name = self.name(token);
+        let mut candidates = self
+            .declarations
+            .iter()
+            .filter(|declaration| self.name(&declaration.identifier) == name);
+
+        candidates
+            .clone()
+            .rev()
+            .find(|declaration| {
+                declaration.identifier == *token || declaration.statement.end <= token.offset
+            })
+            .or_else(|| candidates.next())
+    }
+}
+
+/// Language server speaking LSP over a pair of streams, offering diagnostics,
+/// hover, go-to-definition and document symbols.
+pub struct Server {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    documents: HashMap<String, Document>,
+    natives: HashMap<String, usize>,
+}
+
+impl Server {
+    pub fn new(input: impl BufRead + 'static, output: impl Write + 'static) -> Self {
+        let natives = ProgramContext::new().stack_frames()[0]
+            .variables
+            .iter()
+            .filter_map(|(name, value)| match value {
+                Object::NativeFunction(function) => Some((name.to_string(), function.arity)),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            input: Box::new(input),
+            output: Box::new(output),
+            documents: HashMap::new(),
+            natives,
+        }
+    }
+
+    /// Serves requests until the client sends `exit` or closes the input.
+    pub fn run(&mut self) -> Result<()> {
+        while let Some(body) = self.receive()? {
+            let message = match json::parse(&body) {
+                Ok(message) => message,
+                Err(error) => {
+                    self.respond_error(Value::Null, PARSE_ERROR, &error.message)?;
+                    continue;
+                }
+            };
+
+            if message.get("method").as_str() == Some("exit") {
+                break;
+            }
+
+            self.handle(&message)?;
+        }
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Option<String>> {
+        let mut length = None;
+
+        loop {
+            let mut header = String::new();
+            if self.input.read_line(&mut header)? == 0 {
+                return Ok(None);
+            }
+
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+
+        let Some(length) = length else {
+            return Err(Error::runtime("Missing Content-Length header"));
+        };
+
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+
+        String::from_utf8(body)
+            .map(Some)
+            .map_err(|_| Error::runtime("Message is not valid UTF-8"))
+    }
+
+    fn send(&mut self, message: Value) -> Result<()> {
+        let body = message.to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()?;
+
+        Ok(())
+    }
+
+    fn respond(&mut self, id: Value, result: Value) -> Result<()> {
+        self.send(Value::object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("result", result),
+        ]))
+    }
+
+    fn respond_error(&mut self, id: Value, code: i32, message: &str) -> Result<()> {
+        let error = Value::object([
+            ("code", Value::Number(code as f64)),
+            ("message", Value::from(message)),
+        ]);
+
+        self.send(Value::object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("error", error),
+        ]))
+    }
+
+    fn handle(&mut self, message: &Value) -> Result<()> {
+        let id = message.get("id").clone();
+        let params = message.get("params");
+        let uri = params
+            .get("textDocument")
+            .get("uri")
+            .as_str()
+            .unwrap_or_default();
+
+        let result = match message.get("method").as_str().unwrap_or_default() {
+            "initialize" => Self::capabilities(),
+            "shutdown" => Value::Null,
+            "textDocument/didOpen" => {
+                let text = params.get("textDocument").get("text").as_str();
+                return self.update(uri, text.unwrap_or_default());
+            }
+            "textDocument/didChange" => {
+                let changes = params.get("contentChanges").as_array();
+                let text = changes
+                    .last()
+                    .and_then(|change| change.get("text").as_str());
+                return self.update(uri, text.unwrap_or_default());
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return self.publish_diagnostics(uri, Vec::new());
+            }
+            "textDocument/hover" => self.hover(uri, params.get("position")),
+            "textDocument/definition" => self.definition(uri, params.get("position")),
+            "textDocument/documentSymbol" => self.document_symbols(uri),
+            method if id != Value::Null => {
+                let message = format!("Unknown method {}", method);
+                return self.respond_error(id, METHOD_NOT_FOUND, &message);
+            }
+            _ => return Ok(()),
+        };
+
+        self.respond(id, result)
+    }
+
+    fn capabilities() -> Value {
+        let capabilities = Value::object([
+            ("textDocumentSync", Value::from(1usize)),
+            ("hoverProvider", Value::from(true)),
+            ("definitionProvider", Value::from(true)),
+            ("documentSymbolProvider", Value::from(true)),
+        ]);
+
+        Value::object([
+            ("capabilities", capabilities),
+            ("serverInfo", Value::object([("name", Value::from("lang"))])),
+        ])
+    }
+
+    fn update(&mut self, uri: &str, text: &str) -> Result<()> {
+        let document = Document::new(text.to_string());
+        let diagnostics = document
+            .error
+            .iter()
+            .map(|error| {
+                let span = error.span.unwrap_or_default();
+                Value::object([
+                    ("range", document.range(span)),
+                    ("severity", Value::from(SEVERITY_ERROR)),
+                    ("source", Value::from("lang")),
+                    ("message", Value::from(error.message.as_str())),
+                ])
+            })
+            .collect();
+
+        self.documents.insert(uri.to_string(), document);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Value>) -> Result<()> {
+        let params = Value::object([
+            ("uri", Value::from(uri)),
+            ("diagnostics", Value::from(diagnostics)),
+        ]);
+
+        self.send(Value::object([
+            ("jsonrpc", Value::from("2.0")),
+            ("method", Value::from("textDocument/publishDiagnostics")),
+            ("params", params),
+        ]))
+    }
+
+    fn hover(&self, uri: &str, position: &Value) -> Value {
+        let Some(document) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+
+        let Some(token) = document.identifier_at(position) else {
+            return Value::Null;
+        };
+
+        let name = document.name(&token);
+        let contents = match (document.declaration_of(&token), self.natives.get(name)) {
+            (Some(declaration), _) => {
+                let (noun, verb) = match declaration.kind {
+                    DeclarationKind::Variable => ("variable", "declared"),
+                    DeclarationKind::Import => ("module", "imported"),
+                    DeclarationKind::CatchParameter => ("exception", "caught"),
+                };
+                let line = document.lines.line(declaration.identifier.offset);
+                format!("{} `{}` {} on line {}", noun, name, verb, line)
+            }
+            (None, Some(arity)) => format!(
+                "native function `{}` taking {} argument{}",
+                name,
+                arity,
+                if *arity == 1 { "" } else { "s" }
+            ),
+            (None, None) => format!("undefined variable `{}`", name),
+        };
+
+        Value::object([
+            (
+                "contents",
+                Value::object([
+                    ("kind", Value::from("markdown")),
+                    ("value", Value::from(contents)),
+                ]),
+            ),
+            ("range", document.range(token.span())),
+        ])
+    }
+
+    fn definition(&self, uri: &str, position: &Value) -> Value {
+        let Some(document) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+
+        let Some(declaration) = document
+            .identifier_at(position)
+            .and_then(|token| document.declaration_of(&token))
+        else {
+            return Value::Null;
+        };
+
+        Value::object([
+            ("uri", Value::from(uri)),
+            ("range", document.range(declaration.identifier.span())),
+        ])
+    }
+
+    fn document_symbols(&self, uri: &str) -> Value {
+        let Some(document) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+
+        let symbols = document
+            .declarations
+            .iter()
+            .map(|declaration| {
+                Value::object([
+                    ("name", Value::from(document.name(&declaration.identifier))),
+                    (
+                        "kind",
+                        Value::from(match declaration.kind {
+                            DeclarationKind::Import => SYMBOL_MODULE,
+                            _ => SYMBOL_VARIABLE,
+                        }),
+                    ),
+                    ("range", document.range(declaration.statement)),
+                    (
+                        "selectionRange",
+                        document.range(declaration.identifier.span()),
+                    ),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        Value::from(symbols)
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use std::io::Cursor;
+
+    #[allow(unused_imports)]
+    use crate::json::{self, Value};
+
+    #[allow(unused_imports)]
+    use super::Server;
+
+    #[allow(unused_imports)]
+    use crate::interpreter::io::SharedBuffer;
+
+    #[allow(dead_code)]
+    fn session(messages: &[&str]) -> Vec<Value> {
+        let input = messages
+            .iter()
+            .map(|body| format!("Content-Length: {}\r\n\r\n{}", body.len(), body))
+            .collect::<String>();
+
+        let output = SharedBuffer::new();
+        Server::new(Cursor::new(input), output.clone())
+            .run()
+            .unwrap();
+
+        output
+            .contents()
+            .split("Content-Length: ")
+            .skip(1)
+            .map(|frame| json::parse(frame.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    fn open(text: &str) -> String {
+        let document = Value::object([
+            ("uri", Value::from("file:///a.lang")),
+            ("text", Value::from(text)),
+        ]);
+
+        Value::object([
+            ("method", Value::from("textDocument/didOpen")),
+            ("params", Value::object([("textDocument", document)])),
+        ])
+        .to_string()
+    }
+
+    #[allow(dead_code)]
+    fn request(id: usize, method: &str, line: usize, character: usize) -> String {
+        let params = Value::object([
+            (
+                "textDocument",
+                Value::object([("uri", Value::from("file:///a.lang"))]),
+            ),
+            (
+                "position",
+                Value::object([
+                    ("line", Value::from(line)),
+                    ("character", Value::from(character)),
+                ]),
+            ),
+        ]);
+
+        Value::object([
+            ("id", Value::from(id)),
+            ("method", Value::from(method)),
+            ("params", params),
+        ])
+        .to_string()
+    }
+
+    #[test]
+    fn should_publish_parser_diagnostics() {
+        let responses = session(&[
+            r#"{"id":1,"method":"initialize","params":{}}"#,
+            &open("var a = 1;\nprint a +;"),
+            r#"{"method":"exit"}"#,
+        ]);
+
+        assert_eq!(
+            responses[0]
+                .get("result")
+                .get("capabilities")
+                .get("hoverProvider"),
+            &Value::Boolean(true)
+        );
+
+        let diagnostics = responses[1].get("params").get("diagnostics").as_array();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].get("message").as_str(),
+            Some("Expected a primary expression")
+        );
+        assert_eq!(
+            diagnostics[0].get("range").get("start").to_string(),
+            r#"{"line":1,"character":9}"#
+        );
+    }
+
+    #[test]
+    fn should_answer_hover_definition_and_symbol_requests() {
+        let responses = session(&[
+            &open("var count = 1;\nprint str(count);"),
+            &request(1, "textDocument/hover", 1, 12),
+            &request(2, "textDocument/hover", 1, 7),
+            &request(3, "textDocument/definition", 1, 12),
+            &request(4, "textDocument/documentSymbol", 0, 0),
+            &request(5, "textDocument/unknown", 0, 0),
+        ]);
+
+        assert_eq!(
+            responses[0].get("params").get("diagnostics").as_array(),
+            &[]
+        );
+
+        assert_eq!(
+            responses[1]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("variable `count` declared on line 1")
+        );
+        assert_eq!(
+            responses[2]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("native function `str` taking 1 argument")
+        );
+        assert_eq!(
+            responses[3].get("result").get("range").to_string(),
+            r#"{"start":{"line":0,"character":4},"end":{"line":0,"character":9}}"#
+        );
+
+        let symbols = responses[4].get("result").as_array();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].get("name").as_str(), Some("count"));
+        assert_eq!(
+            symbols[0].get("range").get("end").to_string(),
+            r#"{"line":0,"character":14}"#
+        );
+
+        assert_eq!(
+            responses[5].get("error").get("code"),
+            &Value::Number(-32601.0)
+        );
+    }
+
+    #[test]
+    fn should_declare_import_aliases_and_catch_parameters() {
+        let responses = session(&[
+            &open("import \"util.lang\" as util;\ntry {\n    util.f;\n} catch (e) {\n    print e.message;\n}"),
+            &request(1, "textDocument/hover", 2, 5),
+            &request(2, "textDocument/hover", 4, 11),
+            &request(3, "textDocument/definition", 4, 10),
+            &request(4, "textDocument/documentSymbol", 0, 0),
+        ]);
+
+        assert_eq!(
+            responses[1]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("module `util` imported on line 1")
+        );
+        assert_eq!(
+            responses[2]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("exception `e` caught on line 4")
+        );
+        assert_eq!(
+            responses[3].get("result").get("range").to_string(),
+            r#"{"start":{"line":3,"character":9},"end":{"line":3,"character":10}}"#
+        );
+
+        let symbols = responses[4].get("result").as_array();
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].get("kind"), &Value::Number(2.0));
+        assert_eq!(symbols[1].get("name").as_str(), Some("e"));
+    }
+
+    #[test]
+    fn should_not_resolve_property_names_as_variables() {
+        let responses = session(&[
+            &open("var f = 1;\nvar m = nil;\nprint m.f;\nprint m?.f;"),
+            &request(1, "textDocument/hover", 2, 8),
+            &request(2, "textDocument/definition", 3, 9),
+            &request(3, "textDocument/definition", 2, 6),
+        ]);
+
+        assert_eq!(responses[1].get("result"), &Value::Null);
+        assert_eq!(responses[2].get("result"), &Value::Null);
+        assert_eq!(
+            responses[3].get("result").get("range").to_string(),
+            r#"{"start":{"line":1,"character":4},"end":{"line":1,"character":5}}"#
+        );
+    }
+
+    #[test]
+    fn should_count_positions_in_utf16_code_units() {
+        let responses = session(&[
+            &open("var s = \"😀é\"; var count = 1;\nprint count + \"é\" +;"),
+            &request(1, "textDocument/hover", 0, 20),
+            &request(2, "textDocument/definition", 1, 7),
+        ]);
+
+        let diagnostics = responses[0].get("params").get("diagnostics").as_array();
+        assert_eq!(
+            diagnostics[0].get("range").get("start").to_string(),
+            r#"{"line":1,"character":19}"#
+        );
+
+        assert_eq!(
+            responses[1]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("variable `count` declared on line 1")
+        );
+        assert_eq!(
+            responses[2].get("result").get("range").to_string(),
+            r#"{"start":{"line":0,"character":19},"end":{"line":0,"character":24}}"#
+        );
+    }
+}
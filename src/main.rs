@@ -1,30 +1,411 @@
-use std::io::{stdin, stdout, Write};
+use std::env;
+use std::fs;
+use std::io::{stdin, stdout, BufReader};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
 
-pub mod cursor;
-pub mod interpreter;
-pub mod lexer;
-pub mod parser;
+use lang::debugger::Debugger;
+use lang::editor::Editor;
+use lang::error::{Error, Result};
+use lang::formatter;
+use lang::interpreter::object::Object;
+use lang::interpreter::ProgramContext;
+use lang::json::Value;
+use lang::lexer;
+use lang::lexer::token::Token;
+use lang::linter;
+use lang::lsp::Server;
+use lang::parser;
+use lang::parser::printer::DebugPrint;
+use lang::profiler::Profiler;
+use lang::source::LineIndex;
 
-fn main() {
-    let mut program = interpreter::ProgramContext::new();
-    let mut line = String::new();
+const REPL_HELP: &str = "\
+:vars           list the variables in every frame
+:reset          discard all variables
+:load <file>    run a script in this session
+:tokens <src>   show the tokens of some source
+:ast <src>      show the syntax tree of some source
+:time <src>     run some source and report how long it took
+:help           show this help
+exit            leave the REPL";
+
+enum Dump {
+    Tokens,
+    SExpression,
+    Json,
+}
+
+enum Mode<'a> {
+    Run,
+    Debug,
+    Profile(&'a str),
+}
+
+fn main() -> ExitCode {
+    let arguments = env::args().skip(1).collect::<Vec<String>>();
+
+    match arguments.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+        [] => repl(),
+        ["lsp"] => serve(),
+        ["fmt", path] => format(path, false),
+        ["fmt", "--check", path] => format(path, true),
+        ["check", path] => check(path),
+        ["lint", path] => lint(path),
+        ["--dump-tokens", path] => dump(path, Dump::Tokens),
+        ["--dump-ast" | "--dump-ast=sexpr", path] => dump(path, Dump::SExpression),
+        ["--dump-ast=json", path] => dump(path, Dump::Json),
+        ["--debug", path] => run(path, Mode::Debug),
+        ["--profile", output, path] => run(path, Mode::Profile(output)),
+        [path] if !path.starts_with('-') => run(path, Mode::Run),
+        _ => {
+            eprintln!("Usage: lang [--debug | --profile <folded output>] [file]");
+            eprintln!("       lang [--dump-tokens | --dump-ast=sexpr|json] <file>");
+            eprintln!("       lang fmt [--check] <file>");
+            eprintln!("       lang check <file>");
+            eprintln!("       lang lint <file>");
+            eprintln!("       lang lsp");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn repl() -> ExitCode {
+    let mut program = ProgramContext::new();
+    let mut entries = 0;
+    let mut editor =
+        Editor::new(env::var_os("HOME").map(|home| Path::new(&home).join(".lang_history")));
 
     loop {
-        print!(">> ");
+        let words = program
+            .stack_frames()
+            .iter()
+            .flat_map(|frame| frame.variables.keys().map(|name| name.to_string()))
+            .collect::<Vec<String>>();
 
-        stdout().flush().unwrap();
-        stdin().read_line(&mut line).unwrap();
+        let line = match editor.read_line(">> ", &words) {
+            Ok(Some(line)) => line,
+            Ok(None) => break ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{}", error);
+                break ExitCode::FAILURE;
+            }
+        };
 
         let source = line.trim();
 
         if source.is_empty() {
             continue;
         } else if source == "exit" {
-            break;
+            break ExitCode::SUCCESS;
+        }
+
+        entries += 1;
+        let name = format!("<repl:{}>", entries);
+
+        if source.starts_with(':') {
+            meta_command(&mut program, &name, source);
+        } else {
+            let result = program.interpret_named(&name, source);
+            print_result(&program, result);
+        }
+    }
+}
+
+fn meta_command(program: &mut ProgramContext, name: &str, line: &str) {
+    let (command, argument) = match line.split_once(' ') {
+        Some((command, argument)) => (command, argument.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        ":vars" => {
+            for frame in program.stack_frames() {
+                let mut variables = frame
+                    .variables
+                    .iter()
+                    .filter(|(_, value)| !matches!(value, Object::NativeFunction(_)))
+                    .collect::<Vec<_>>();
+                variables.sort_by_key(|(name, _)| name.as_str());
+
+                for (name, value) in variables {
+                    println!("{} = {}", name, value.repr());
+                }
+            }
+        }
+        ":reset" => *program = ProgramContext::new(),
+        ":load" => match fs::read_to_string(argument) {
+            Ok(source) => {
+                if let Err(error) = program.interpret_file(argument, &source) {
+                    report_error(argument, &source, &error);
+                }
+            }
+            Err(error) => eprintln!("Cannot read {}: {}", argument, error),
+        },
+        ":tokens" => match lexer::tokenize(argument) {
+            Ok(tokens) => print_tokens(argument, &tokens),
+            Err(error) => eprintln!("{}", error),
+        },
+        ":ast" => match lexer::tokenize(argument).and_then(parser::parse) {
+            Ok(statements) => {
+                for statement in statements {
+                    println!("{}", statement.debug_print(argument));
+                }
+            }
+            Err(error) => eprintln!("{}", error),
+        },
+        ":time" => {
+            let start = Instant::now();
+            let result = program.interpret_named(name, argument);
+            let elapsed = start.elapsed();
+
+            print_result(program, result);
+            println!("Took {:.3}ms", elapsed.as_secs_f64() * 1000.0);
+        }
+        ":help" => println!("{}", REPL_HELP),
+        _ => eprintln!("Unknown command {}, try :help", command),
+    }
+}
+
+fn print_result(program: &ProgramContext, result: Result<Object>) {
+    match result {
+        Ok(Object::None) => {}
+        Ok(value) => println!("{}", value.repr()),
+        Err(error) => {
+            report_traceback(&error);
+
+            match error.span.and_then(|span| program.sources().location(span)) {
+                Some(location) => eprintln!(
+                    "{}:{}:{}: {}",
+                    location.path, location.line, location.column, error
+                ),
+                None => eprintln!("{}", error),
+            }
         }
+    }
+}
 
-        program.interpret(source);
+fn print_tokens(source: &str, tokens: &[Token]) {
+    for token in tokens {
+        let value = &source[token.offset..token.end];
+        println!(
+            "{:?} {}..{} {:?}",
+            token.kind, token.offset, token.end, value
+        );
+    }
+}
+
+fn dump(path: &str, dump: Dump) -> ExitCode {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Cannot read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let tokens = match lexer::tokenize(&source) {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            report_error(path, &source, &error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Dump::Tokens = dump {
+        print_tokens(&source, &tokens);
+        return ExitCode::SUCCESS;
+    }
+
+    let statements = match parser::parse(tokens) {
+        Ok(statements) => statements,
+        Err(error) => {
+            report_error(path, &source, &error);
+            return ExitCode::FAILURE;
+        }
+    };
 
-        line.clear();
+    match dump {
+        Dump::Tokens => {}
+        Dump::SExpression => {
+            for statement in statements {
+                println!("{}", statement.debug_print(&source));
+            }
+        }
+        Dump::Json => {
+            let statements = statements
+                .iter()
+                .map(|statement| statement.json_print(&source))
+                .collect::<Vec<_>>();
+            println!("{}", Value::from(statements));
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn check(path: &str) -> ExitCode {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Cannot read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let errors = match ProgramContext::new().check(&source) {
+        Ok(errors) => errors,
+        Err(error) => vec![error],
+    };
+
+    for error in errors.iter() {
+        report_error(path, &source, error);
+    }
+
+    if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn lint(path: &str) -> ExitCode {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Cannot read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let warnings = match linter::lint(&source) {
+        Ok(warnings) => warnings,
+        Err(error) => {
+            report_error(path, &source, &error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let lines = LineIndex::new(&source);
+    for warning in warnings.iter() {
+        let line = lines.line(warning.span.offset);
+        let column = lines.column(warning.span.offset);
+        eprintln!("{}:{}:{}: {}", path, line, column, warning);
+    }
+
+    if warnings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn format(path: &str, check: bool) -> ExitCode {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Cannot read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let formatted = match formatter::format(&source) {
+        Ok(formatted) => formatted,
+        Err(error) => {
+            report_error(path, &source, &error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if formatted == source {
+        return ExitCode::SUCCESS;
+    }
+
+    if check {
+        eprintln!("{} is not formatted", path);
+        return ExitCode::FAILURE;
+    }
+
+    match fs::write(path, formatted) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Cannot write {}: {}", path, error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn serve() -> ExitCode {
+    match Server::new(BufReader::new(stdin()), stdout()).run() {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(path: &str, mode: Mode) -> ExitCode {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("Cannot read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut program = ProgramContext::new();
+
+    let profiler = Profiler::new();
+
+    match mode {
+        Mode::Run => {}
+        Mode::Debug => {
+            let debugger = Debugger::new(BufReader::with_capacity(1, stdin()), stdout());
+            program.set_hook(Some(Box::new(debugger)));
+        }
+        Mode::Profile(_) => program.set_hook(Some(Box::new(profiler.clone()))),
+    }
+
+    let result = program.interpret_file(path, &source);
+
+    if let Mode::Profile(output) = mode {
+        eprint!("{}", profiler.report());
+
+        if let Err(error) = fs::write(output, profiler.folded()) {
+            eprintln!("Cannot write {}: {}", output, error);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(error) => {
+            report_error(path, &source, &error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the frames of errors that unwound through more than the script.
+fn report_traceback(error: &Error) {
+    if error.trace.len() > 1 {
+        eprint!("{}", error.traceback());
+    }
+}
+
+fn report_error(path: &str, source: &str, error: &Error) {
+    report_traceback(error);
+
+    match error.span {
+        Some(span) => {
+            let lines = LineIndex::new(source);
+            let line = lines.line(span.offset);
+            let column = lines.column(span.offset);
+
+            eprintln!("{}:{}:{}: {}", path, line, column, error);
+        }
+        None => eprintln!("{}: {}", path, error),
     }
 }
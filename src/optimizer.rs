@@ -0,0 +1,312 @@
+use std::mem;
+
+use crate::interpreter::object::Object;
+use crate::interpreter::operators;
+use crate::lexer::token::TokenKind;
+use crate::parser::tree::Constant;
+use crate::parser::tree::ExpressionNode;
+use crate::parser::tree::Statement;
+use crate::parser::tree::StatementNode;
+
+enum Optimized {
+    Node(ExpressionNode),
+    Replace(usize),
+}
+
+struct Optimizer<'a> {
+    statement: &'a mut Statement,
+    source: &'a str,
+}
+
+impl Optimizer<'_> {
+    /// Optimizes the subtree rooted at `index` in place and returns the index
+    /// of the node that should take its place.
+    fn optimize(&mut self, index: usize) -> usize {
+        let placeholder = StatementNode::Expression(ExpressionNode::Constant(Constant::Nil));
+        let node = mem::replace(&mut self.statement.tree[index], placeholder);
+
+        let node = match node {
+            StatementNode::Expression(expression) => match self.optimize_expression(expression) {
+                Optimized::Node(expression) => StatementNode::Expression(expression),
+                Optimized::Replace(replacement) => return replacement,
+            },
+            StatementNode::VariableDefinition(mut definition) => {
+                definition.expression = self.optimize(definition.expression);
+                StatementNode::VariableDefinition(definition)
+            }
+            StatementNode::Print(expression) => StatementNode::Print(self.optimize(expression)),
+            StatementNode::Export(declaration) => StatementNode::Export(self.optimize(declaration)),
+            StatementNode::Block(statements) => StatementNode::Block(
+                statements
+                    .into_iter()
+                    .map(|statement| self.optimize(statement))
+                    .collect(),
+            ),
+            StatementNode::Try(mut statement) => {
+                statement.body = self.optimize(statement.body);
+                if let Some(handler) = statement.handler.as_mut() {
+                    handler.body = self.optimize(handler.body);
+                }
+                statement.finalizer = statement
+                    .finalizer
+                    .map(|finalizer| self.optimize(finalizer));
+                StatementNode::Try(statement)
+            }
+            StatementNode::Throw(expression) => StatementNode::Throw(self.optimize(expression)),
+            node @ StatementNode::Import(_) => node,
+        };
+
+        self.statement.tree[index] = node;
+        index
+    }
+
+    fn optimize_expression(&mut self, expression: ExpressionNode) -> Optimized {
+        match expression {
+            ExpressionNode::Grouping(inner) => Optimized::Replace(self.optimize(inner)),
+            ExpressionNode::Literal(literal) => {
+                Optimized::Node(ExpressionNode::Constant(literal.constant(self.source)))
+            }
+            ExpressionNode::Unary(mut unary) => {
+                unary.right = self.optimize(unary.right);
+
+                if unary.operator.kind == TokenKind::Minus {
+                    if let Some(ExpressionNode::Unary(inner)) = self.get_expression(unary.right) {
+                        if inner.operator.kind == TokenKind::Minus && self.is_numeric(inner.right) {
+                            return Optimized::Replace(inner.right);
+                        }
+                    }
+                }
+
+                let folded = self
+                    .get_constant(unary.right)
+                    .and_then(|right| operators::unary(unary.operator.kind, &right).ok());
+
+                self.fold(folded, ExpressionNode::Unary(unary))
+            }
+            ExpressionNode::Binary(mut binary) => {
+                binary.left = self.optimize(binary.left);
+                binary.right = self.optimize(binary.right);
+
+                let operator = binary.operator.kind;
+                let left = self.get_constant(binary.left);
+                let right = self.get_constant(binary.right);
+
+                if operator == TokenKind::QuestionQuestion {
+                    return match (left, right) {
+                        (Some(Object::None), _) => Optimized::Replace(binary.right),
+                        (Some(_), _) | (_, Some(Object::None)) => Optimized::Replace(binary.left),
+                        _ => Optimized::Node(ExpressionNode::Binary(binary)),
+                    };
+                }
+
+                if let Some(replacement) = self.simplify_identity(
+                    binary.left,
+                    operator,
+                    binary.right,
+                    left.as_ref(),
+                    right.as_ref(),
+                ) {
+                    return Optimized::Replace(replacement);
+                }
+
+                let folded = left
+                    .zip(right)
+                    .and_then(|(left, right)| operators::binary(&left, operator, &right).ok());
+
+                self.fold(folded, ExpressionNode::Binary(binary))
+            }
+            ExpressionNode::Interpolation(parts) => {
+                let parts = parts
+                    .into_iter()
+                    .map(|part| self.optimize(part))
+                    .collect::<Vec<_>>();
+
+                let folded = parts
+                    .iter()
+                    .map(|part| self.get_constant(*part).map(|value| value.str()))
+                    .collect::<Option<String>>()
+                    .map(Object::String);
+
+                self.fold(folded, ExpressionNode::Interpolation(parts))
+            }
+            ExpressionNode::Assignment(mut assignment) => {
+                assignment.value = self.optimize(assignment.value);
+                Optimized::Node(ExpressionNode::Assignment(assignment))
+            }
+            ExpressionNode::Call(mut call) => {
+                call.callee = self.optimize(call.callee);
+                for argument in call.arguments.iter_mut() {
+                    *argument = self.optimize(*argument);
+                }
+                Optimized::Node(ExpressionNode::Call(call))
+            }
+            ExpressionNode::Get(mut get) => {
+                get.object = self.optimize(get.object);
+                Optimized::Node(ExpressionNode::Get(get))
+            }
+            expression @ (ExpressionNode::Variable(_)
+            | ExpressionNode::Update(_)
+            | ExpressionNode::Constant(_)) => Optimized::Node(expression),
+        }
+    }
+
+    /// Drops operations that leave a number unchanged. Only operands known to
+    /// evaluate to numbers qualify, as `"a" * 1` must still fail at runtime.
+    fn simplify_identity(
+        &self,
+        left_index: usize,
+        operator: TokenKind,
+        right_index: usize,
+        left: Option<&Object>,
+        right: Option<&Object>,
+    ) -> Option<usize> {
+        let is_one = |value: Option<&Object>| matches!(value, Some(Object::Number(n)) if *n == 1.0);
+        let is_zero = |value: Option<&Object>| matches!(value, Some(Object::Number(n)) if n.to_bits() == 0.0f32.to_bits());
+
+        let replacement = match operator {
+            TokenKind::Star if is_one(right) => left_index,
+            TokenKind::Star if is_one(left) => right_index,
+            TokenKind::Slash | TokenKind::StarStar if is_one(right) => left_index,
+            TokenKind::Minus if is_zero(right) => left_index,
+            _ => return None,
+        };
+
+        self.is_numeric(replacement).then_some(replacement)
+    }
+
+    fn is_numeric(&self, index: usize) -> bool {
+        match self.get_expression(index) {
+            Some(ExpressionNode::Constant(Constant::Number(_))) => true,
+            Some(ExpressionNode::Update(_)) => true,
+            Some(ExpressionNode::Unary(unary)) => {
+                matches!(unary.operator.kind, TokenKind::Minus | TokenKind::Tilde)
+            }
+            Some(ExpressionNode::Binary(binary)) => matches!(
+                binary.operator.kind,
+                TokenKind::Minus
+                    | TokenKind::Star
+                    | TokenKind::Slash
+                    | TokenKind::SlashSlash
+                    | TokenKind::Percent
+                    | TokenKind::StarStar
+                    | TokenKind::Ampersand
+                    | TokenKind::Pipe
+                    | TokenKind::Caret
+                    | TokenKind::LessLess
+                    | TokenKind::GreaterGreater
+            ),
+            _ => false,
+        }
+    }
+
+    fn fold(&self, folded: Option<Object>, expression: ExpressionNode) -> Optimized {
+        let constant = match folded {
+            Some(Object::Number(value)) => Constant::Number(value),
+            Some(Object::String(value)) => Constant::String(value),
+            Some(Object::Boolean(value)) => Constant::Boolean(value),
+            Some(Object::None) => Constant::Nil,
+            _ => return Optimized::Node(expression),
+        };
+
+        Optimized::Node(ExpressionNode::Constant(constant))
+    }
+
+    fn get_expression(&self, index: usize) -> Option<&ExpressionNode> {
+        match self.statement.tree.get(index) {
+            Some(StatementNode::Expression(expression)) => Some(expression),
+            _ => None,
+        }
+    }
+
+    fn get_constant(&self, index: usize) -> Option<Object> {
+        match self.get_expression(index) {
+            Some(ExpressionNode::Constant(constant)) => Some(Object::from(constant)),
+            _ => None,
+        }
+    }
+}
+
+/// Folds constant subexpressions, removes groupings and drops numeric
+/// identities such as `x * 1`. Operations that would fail are left in place so
+/// the error is still reported, with its span, when the statement runs.
+pub fn optimize(statement: &mut Statement, source: &str) {
+    let root = statement.root_index;
+    let mut optimizer = Optimizer { statement, source };
+
+    optimizer.statement.root_index = optimizer.optimize(root);
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::optimize;
+
+    #[allow(unused_imports)]
+    use crate::interpreter::ProgramContext;
+
+    #[allow(unused_imports)]
+    use crate::lexer::tokenize;
+
+    #[allow(unused_imports)]
+    use crate::parser::parse;
+
+    #[allow(unused_imports)]
+    use crate::parser::printer::DebugPrint;
+
+    #[allow(dead_code)]
+    fn optimized(source: &str) -> Vec<String> {
+        parse(tokenize(source).unwrap())
+            .unwrap()
+            .iter_mut()
+            .map(|statement| {
+                optimize(statement, source);
+                statement.debug_print(source)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_fold_constant_expressions() {
+        assert_eq!(
+            optimized(
+                "print -(1 + 2) * 3 ** 2; print \"a${1 < 2}\" + 1; print nil ?? !true; print \"é${1}\" + \"ü\";"
+            ),
+            vec![
+                "(print -27)",
+                "(print \"atrue1\")",
+                "(print false)",
+                "(print \"é1ü\")"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_remove_groupings_and_numeric_identities() {
+        assert_eq!(
+            optimized(
+                "print ((a)); print (a - 1) * 1; print -(-(~a)); print a * 1; print b ?? nil;"
+            ),
+            vec![
+                "(print a)",
+                "(print (- a 1))",
+                "(print (~ a))",
+                "(print (* a 1))",
+                "(print b)"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_leave_failing_operations_to_the_interpreter() {
+        assert_eq!(
+            optimized("print (1 - \"a\") + 2; print 1 << 70;"),
+            vec!["(print (+ (- 1 \"a\") 2))", "(print (<< 1 70))"]
+        );
+
+        let error = ProgramContext::new()
+            .interpret("var a = 1;\nprint 2 * (1 - \"a\");")
+            .err()
+            .unwrap();
+        assert_eq!(error.span.map(|span| span.offset), Some(24));
+    }
+}
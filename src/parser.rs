@@ -4,157 +4,451 @@ pub mod rules;
 pub mod tree;
 
 use self::builder::StatementListBuilder;
+use self::rules::is_assignment_token;
+use self::rules::is_bitwise_and_token;
+use self::rules::is_bitwise_or_token;
+use self::rules::is_bitwise_xor_token;
+use self::rules::is_call_token;
+use self::rules::is_coalesce_token;
 use self::rules::is_comparison_token;
 use self::rules::is_equality_token;
 use self::rules::is_factor_token;
+use self::rules::is_interpolation_continue_token;
+use self::rules::is_power_token;
+use self::rules::is_shift_token;
 use self::rules::is_term_token;
 use self::rules::is_unary_token;
+use self::rules::is_update_token;
+use self::tree::Catch;
 use self::tree::Statement;
 
 use crate::cursor::Cursor;
 use crate::cursor::ToCursor;
 
+use crate::error::Error;
+use crate::error::Result;
+use crate::lexer::token::Span;
 use crate::lexer::token::Token;
 use crate::lexer::token::TokenKind;
 use crate::parser::rules::is_primary_token;
+use crate::source::SourceId;
 
 struct RecursiveDescentParser {
     cursor: Cursor<Token>,
     builder: StatementListBuilder,
+    end: usize,
+    source: SourceId,
+    depth: usize,
 }
 
 impl RecursiveDescentParser {
     const TERMINATOR_TOKEN: Token = Token::from_kind(TokenKind::None);
+    /// Deepest nesting of blocks, groupings and prefix operators accepted,
+    /// so that hostile input cannot overflow the stack while parsing.
+    const MAX_DEPTH: usize = 128;
 
     pub fn new(tokens: Vec<Token>) -> RecursiveDescentParser {
         RecursiveDescentParser {
+            end: tokens.last().map_or(0, |token| token.end),
+            source: tokens
+                .last()
+                .map_or(SourceId::UNKNOWN, |token| token.source),
             cursor: tokens.to_cursor(Self::TERMINATOR_TOKEN),
             builder: StatementListBuilder::new(),
+            depth: 0,
         }
     }
 
-    pub fn parse(mut self) -> Vec<Statement> {
+    pub fn parse(mut self) -> Result<Vec<Statement>> {
         loop {
             if self.cursor.is_at_end() {
-                break self.builder.statements;
+                break Ok(self.builder.statements);
             }
 
-            self.statement();
+            self.statement()?;
         }
     }
 
-    fn statement(&mut self) {
-        if self.matches(|kind| kind == TokenKind::Var).is_some() {
-            self.variable_definition_statement();
-        } else if self.matches(|kind| kind == TokenKind::Print).is_some() {
-            self.print_statement();
+    fn statement(&mut self) -> Result<()> {
+        self.builder.start_statement();
+        let root_index = self.declaration()?;
+        self.builder.end_statement(root_index);
+
+        Ok(())
+    }
+
+    fn declaration(&mut self) -> Result<usize> {
+        if let Some(keyword) = self.matches(|kind| kind == TokenKind::Var) {
+            self.variable_definition_statement(keyword, None)
+        } else if let Some(export) = self.matches(|kind| kind == TokenKind::Export) {
+            let keyword = self.expect(TokenKind::Var)?;
+            self.variable_definition_statement(keyword, Some(export))
+        } else if let Some(keyword) = self.matches(|kind| kind == TokenKind::Import) {
+            self.import_statement(keyword)
+        } else if let Some(keyword) = self.matches(|kind| kind == TokenKind::Print) {
+            self.print_statement(keyword)
+        } else if let Some(keyword) = self.matches(|kind| kind == TokenKind::Throw) {
+            self.throw_statement(keyword)
+        } else if let Some(keyword) = self.matches(|kind| kind == TokenKind::Try) {
+            self.try_statement(keyword)
+        } else if let Some(open) = self.matches(|kind| kind == TokenKind::LeftBrace) {
+            self.block(open)
         } else {
-            self.expression_statement();
+            self.expression_statement()
         }
     }
 
-    fn variable_definition_statement(&mut self) {
-        let name = self.expect(TokenKind::Identifier);
-        self.expect(TokenKind::Equal);
+    fn variable_definition_statement(
+        &mut self,
+        keyword: Token,
+        export: Option<Token>,
+    ) -> Result<usize> {
+        let name = self.expect(TokenKind::Identifier)?;
+        let annotation = match self.matches(|kind| kind == TokenKind::Colon) {
+            Some(_) => Some(self.expect(TokenKind::Identifier)?),
+            None => None,
+        };
+        self.expect(TokenKind::Equal)?;
+
+        let expression = self.expression()?;
+        let mut variable_definition = self
+            .builder
+            .add_variable_definition(keyword, name, annotation, expression);
+
+        if let Some(export) = export {
+            variable_definition = self
+                .builder
+                .add_export_statement(export, variable_definition);
+        }
 
-        self.builder.start_statement();
+        self.expect(TokenKind::Semicolon)?;
+        Ok(variable_definition)
+    }
 
-        let expression = self.expression();
-        let variable_definition = self.builder.add_variable_definition(name, expression);
+    fn import_statement(&mut self, keyword: Token) -> Result<usize> {
+        let path = self.expect(TokenKind::String)?;
+        self.expect(TokenKind::As)?;
+        let alias = self.expect(TokenKind::Identifier)?;
 
-        self.builder.end_statement(variable_definition);
+        let import_statement = self.builder.add_import_statement(keyword, path, alias);
 
-        self.expect(TokenKind::Semicolon);
+        self.expect(TokenKind::Semicolon)?;
+        Ok(import_statement)
     }
 
-    fn print_statement(&mut self) {
-        self.builder.start_statement();
+    fn print_statement(&mut self, keyword: Token) -> Result<usize> {
+        let expression = self.expression()?;
+        let print_statement = self.builder.add_print_statement(keyword, expression);
 
-        let expression = self.expression();
-        let print_statement = self.builder.add_print_statement(expression);
+        self.expect(TokenKind::Semicolon)?;
+        Ok(print_statement)
+    }
 
-        self.builder.end_statement(print_statement);
+    fn throw_statement(&mut self, keyword: Token) -> Result<usize> {
+        let expression = self.expression()?;
+        let throw_statement = self.builder.add_throw_statement(keyword, expression);
 
-        self.expect(TokenKind::Semicolon);
+        self.expect(TokenKind::Semicolon)?;
+        Ok(throw_statement)
     }
 
-    fn expression_statement(&mut self) {
-        self.builder.start_statement();
-        let root_index = self.expression();
-        self.builder.end_statement(root_index);
+    fn try_statement(&mut self, keyword: Token) -> Result<usize> {
+        let open = self.expect(TokenKind::LeftBrace)?;
+        let body = self.block(open)?;
+
+        let handler = match self.matches(|kind| kind == TokenKind::Catch) {
+            Some(_) => {
+                self.expect(TokenKind::LeftParen)?;
+                let variable = self.expect(TokenKind::Identifier)?;
+                self.expect(TokenKind::RightParen)?;
+
+                let open = self.expect(TokenKind::LeftBrace)?;
+                Some(Catch {
+                    variable,
+                    body: self.block(open)?,
+                })
+            }
+            None => None,
+        };
 
-        self.expect(TokenKind::Semicolon);
+        let finalizer = match self.matches(|kind| kind == TokenKind::Finally) {
+            Some(_) => {
+                let open = self.expect(TokenKind::LeftBrace)?;
+                Some(self.block(open)?)
+            }
+            None => None,
+        };
+
+        if handler.is_none() && finalizer.is_none() {
+            return Err(Error::syntax(
+                "Expected catch or finally after try block",
+                self.next_span(),
+            ));
+        }
+
+        Ok(self
+            .builder
+            .add_try_statement(keyword, body, handler, finalizer))
+    }
+
+    fn block(&mut self, open: Token) -> Result<usize> {
+        let mut statements = Vec::new();
+
+        while !self.cursor.is_at_end() && self.cursor.peek(0).kind != TokenKind::RightBrace {
+            statements.push(self.nested(Self::declaration)?);
+        }
+
+        let close = self.expect(TokenKind::RightBrace)?;
+        Ok(self.builder.add_block(open, statements, close))
+    }
+
+    fn expression_statement(&mut self) -> Result<usize> {
+        let root_index = self.expression()?;
+
+        self.expect(TokenKind::Semicolon)?;
+        Ok(root_index)
     }
 
-    fn expression(&mut self) -> usize {
-        self.equality()
+    fn expression(&mut self) -> Result<usize> {
+        self.nested(Self::assignment)
     }
 
-    fn equality(&mut self) -> usize {
-        let mut left = self.comparison();
+    fn assignment(&mut self) -> Result<usize> {
+        let target = self.coalesce()?;
+
+        if let Some(operator) = self.matches(is_assignment_token) {
+            let value = self.nested(Self::assignment)?;
+            let target = self.assignment_target(target, operator)?;
+            Ok(self.builder.add_assignment(target, operator, value))
+        } else {
+            Ok(target)
+        }
+    }
+
+    fn coalesce(&mut self) -> Result<usize> {
+        let mut left = self.equality()?;
+
+        while let Some(operator) = self.matches(is_coalesce_token) {
+            let right = self.equality()?;
+            left = self.builder.add_binary(left, operator, right);
+        }
+
+        Ok(left)
+    }
+
+    fn equality(&mut self) -> Result<usize> {
+        let mut left = self.comparison()?;
 
         while let Some(operator) = self.matches(is_equality_token) {
-            let right = self.comparison();
+            let right = self.comparison()?;
             left = self.builder.add_binary(left, operator, right);
         }
 
-        left
+        Ok(left)
     }
 
-    fn comparison(&mut self) -> usize {
-        let mut left = self.term();
+    fn comparison(&mut self) -> Result<usize> {
+        let mut left = self.bitwise_or()?;
 
         while let Some(operator) = self.matches(is_comparison_token) {
-            let right = self.term();
+            let right = self.bitwise_or()?;
+            left = self.builder.add_binary(left, operator, right);
+        }
+
+        Ok(left)
+    }
+
+    fn bitwise_or(&mut self) -> Result<usize> {
+        let mut left = self.bitwise_xor()?;
+
+        while let Some(operator) = self.matches(is_bitwise_or_token) {
+            let right = self.bitwise_xor()?;
+            left = self.builder.add_binary(left, operator, right);
+        }
+
+        Ok(left)
+    }
+
+    fn bitwise_xor(&mut self) -> Result<usize> {
+        let mut left = self.bitwise_and()?;
+
+        while let Some(operator) = self.matches(is_bitwise_xor_token) {
+            let right = self.bitwise_and()?;
+            left = self.builder.add_binary(left, operator, right);
+        }
+
+        Ok(left)
+    }
+
+    fn bitwise_and(&mut self) -> Result<usize> {
+        let mut left = self.shift()?;
+
+        while let Some(operator) = self.matches(is_bitwise_and_token) {
+            let right = self.shift()?;
             left = self.builder.add_binary(left, operator, right);
         }
 
-        left
+        Ok(left)
     }
 
-    fn term(&mut self) -> usize {
-        let mut left = self.factor();
+    fn shift(&mut self) -> Result<usize> {
+        let mut left = self.term()?;
+
+        while let Some(operator) = self.matches(is_shift_token) {
+            let right = self.term()?;
+            left = self.builder.add_binary(left, operator, right);
+        }
+
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<usize> {
+        let mut left = self.factor()?;
 
         while let Some(operator) = self.matches(is_term_token) {
-            let right = self.factor();
+            let right = self.factor()?;
             left = self.builder.add_binary(left, operator, right);
         }
 
-        left
+        Ok(left)
     }
 
-    fn factor(&mut self) -> usize {
-        let mut left = self.unary();
+    fn factor(&mut self) -> Result<usize> {
+        let mut left = self.unary()?;
 
         while let Some(operator) = self.matches(is_factor_token) {
-            let right = self.unary();
+            let right = self.unary()?;
             left = self.builder.add_binary(left, operator, right);
         }
 
-        left
+        Ok(left)
     }
 
-    fn unary(&mut self) -> usize {
-        if let Some(operator) = self.matches(is_unary_token) {
-            let right = self.unary();
-            self.builder.add_unary(operator, right)
+    fn unary(&mut self) -> Result<usize> {
+        if let Some(operator) = self.matches(is_update_token) {
+            let operand = self.nested(Self::unary)?;
+            let target = self.assignment_target(operand, operator)?;
+            Ok(self.builder.add_update(target, operator, true))
+        } else if let Some(operator) = self.matches(is_unary_token) {
+            let right = self.nested(Self::unary)?;
+            Ok(self.builder.add_unary(operator, right))
         } else {
-            self.primary()
+            self.power()
         }
     }
 
-    fn primary(&mut self) -> usize {
+    fn power(&mut self) -> Result<usize> {
+        let left = self.postfix()?;
+
+        if let Some(operator) = self.matches(is_power_token) {
+            let right = self.nested(Self::unary)?;
+            Ok(self.builder.add_binary(left, operator, right))
+        } else {
+            Ok(left)
+        }
+    }
+
+    fn postfix(&mut self) -> Result<usize> {
+        let operand = self.call()?;
+
+        if let Some(operator) = self.matches(is_update_token) {
+            let target = self.assignment_target(operand, operator)?;
+            Ok(self.builder.add_update(target, operator, false))
+        } else {
+            Ok(operand)
+        }
+    }
+
+    fn call(&mut self) -> Result<usize> {
+        let mut callee = self.primary()?;
+        let mut optional = false;
+
+        while let Some(token) = self.matches(is_call_token) {
+            optional |= token.kind == TokenKind::QuestionDot;
+
+            if token.kind != TokenKind::LeftParen {
+                let name = self.expect(TokenKind::Identifier)?;
+                callee = self.builder.add_get(callee, token, name, optional);
+            } else {
+                let (arguments, closing) = self.arguments()?;
+                callee = self
+                    .builder
+                    .add_call(callee, token, arguments, closing, optional);
+            }
+        }
+
+        Ok(callee)
+    }
+
+    fn arguments(&mut self) -> Result<(Vec<usize>, Token)> {
+        let mut arguments = Vec::new();
+
+        if let Some(closing) = self.matches(|kind| kind == TokenKind::RightParen) {
+            return Ok((arguments, closing));
+        }
+
+        loop {
+            arguments.push(self.expression()?);
+
+            if self.matches(|kind| kind == TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+
+        let closing = self.expect(TokenKind::RightParen)?;
+        Ok((arguments, closing))
+    }
+
+    fn primary(&mut self) -> Result<usize> {
         if let Some(token) = self.matches(is_primary_token) {
-            self.builder.add_literal(token)
-        } else if self.matches(|kind| kind == TokenKind::LeftParen).is_some() {
-            let expression = self.expression();
-            self.expect(TokenKind::RightParen);
-            self.builder.add_grouping(expression)
+            Ok(self.builder.add_literal(token))
+        } else if let Some(paren) = self.matches(|kind| kind == TokenKind::LeftParen) {
+            let expression = self.expression()?;
+            let closing = self.expect(TokenKind::RightParen)?;
+            Ok(self.builder.add_grouping(paren, expression, closing))
         } else if let Some(token) = self.matches(|kind| kind == TokenKind::Identifier) {
-            self.builder.add_variable(token)
+            Ok(self.builder.add_variable(token))
+        } else if let Some(token) = self.matches(|kind| kind == TokenKind::InterpolationStart) {
+            self.interpolation(token)
         } else {
-            panic!("Expected a primary expression!")
+            let span = self.next_span();
+            Err(Error::syntax("Expected a primary expression", span))
+        }
+    }
+
+    fn interpolation(&mut self, start: Token) -> Result<usize> {
+        let mut parts = vec![self.builder.add_literal(start)];
+
+        loop {
+            parts.push(self.expression()?);
+
+            if let Some(token) = self.matches(is_interpolation_continue_token) {
+                parts.push(self.builder.add_literal(token));
+            } else {
+                let end = self.expect(TokenKind::InterpolationEnd)?;
+                parts.push(self.builder.add_literal(end));
+                break Ok(self.builder.add_interpolation(parts));
+            }
+        }
+    }
+
+    fn nested(&mut self, rule: fn(&mut Self) -> Result<usize>) -> Result<usize> {
+        if self.depth >= Self::MAX_DEPTH {
+            let span = self.next_span();
+            return Err(Error::syntax("Nesting limit exceeded", span));
         }
+
+        self.depth += 1;
+        let result = rule(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn assignment_target(&self, index: usize, operator: Token) -> Result<Token> {
+        self.builder
+            .get_variable(index)
+            .ok_or_else(|| Error::syntax("Invalid assignment target", operator.span()))
     }
 
     fn matches(&mut self, rule: fn(TokenKind) -> bool) -> Option<Token> {
@@ -165,17 +459,34 @@ impl RecursiveDescentParser {
         }
     }
 
-    fn expect(&mut self, kind: TokenKind) -> Token {
+    fn expect(&mut self, kind: TokenKind) -> Result<Token> {
+        let span = self.next_span();
         let next_token = self.cursor.next_or_end();
         if kind == next_token.kind {
-            next_token
+            Ok(next_token)
+        } else {
+            Err(Error::syntax(
+                format!("Expected token kind {:?}", kind),
+                span,
+            ))
+        }
+    }
+
+    fn next_span(&mut self) -> Span {
+        let next_token = self.cursor.peek_first();
+        if next_token.kind == TokenKind::None {
+            Span {
+                offset: self.end,
+                end: self.end,
+                source: self.source,
+            }
         } else {
-            panic!("Expected token kind {:?}", kind)
+            next_token.span()
         }
     }
 }
 
-pub fn parse(tokens: Vec<Token>) -> Vec<Statement> {
+pub fn parse(tokens: Vec<Token>) -> Result<Vec<Statement>> {
     let parser = RecursiveDescentParser::new(tokens);
     parser.parse()
 }
@@ -183,8 +494,13 @@ pub fn parse(tokens: Vec<Token>) -> Vec<Statement> {
 #[allow(unused_imports)]
 mod tests {
 
+    #[allow(unused_imports)]
+    use crate::error::ErrorKind;
     use crate::lexer;
+    use crate::lexer::token::TokenKind;
     use crate::parser;
+    #[allow(unused_imports)]
+    use crate::parser::printer::DebugPrint;
     use crate::parser::tree::ExpressionNode;
     use crate::parser::tree::StatementNode;
 
@@ -192,8 +508,8 @@ mod tests {
     fn should_parse_binary_expression() {
         let source = "1 + 2;";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -209,8 +525,8 @@ mod tests {
     fn should_parse_unary_expression() {
         let source = "-2;";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -226,8 +542,8 @@ mod tests {
     fn should_parse_literal_expression() {
         let source = "12341231;";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -243,8 +559,8 @@ mod tests {
     fn should_parse_grouping_expression() {
         let source = "(12341231 + 123);";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -260,8 +576,8 @@ mod tests {
     fn should_parse_variable_expression() {
         let source = "asdasdsad;";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -277,8 +593,8 @@ mod tests {
     fn should_parse_variable_definition_statement() {
         let source = "var b = 123;";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -291,8 +607,8 @@ mod tests {
     fn should_parse_print_statement() {
         let source = "print 123;";
 
-        let tokens = lexer::tokenize(source);
-        let statements = parser::parse(tokens);
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
 
         assert_eq!(statements.len(), 1);
 
@@ -300,4 +616,271 @@ mod tests {
 
         assert!(matches!(root, Some(StatementNode::Print(_))))
     }
+
+    #[test]
+    fn should_parse_power_expression_as_right_associative() {
+        let source = "-2 ** 3 ** 2;";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        assert_eq!(statements.len(), 1);
+
+        let tree = &statements[0].tree;
+        let root = tree.get(statements[0].root_index);
+
+        let Some(StatementNode::Expression(ExpressionNode::Unary(unary))) = root else {
+            panic!("Expected unary root")
+        };
+
+        let Some(StatementNode::Expression(ExpressionNode::Binary(power))) = tree.get(unary.right)
+        else {
+            panic!("Expected power operand")
+        };
+
+        assert_eq!(power.operator.kind, TokenKind::StarStar);
+        assert!(matches!(
+            tree.get(power.right),
+            Some(StatementNode::Expression(ExpressionNode::Binary(_)))
+        ))
+    }
+
+    #[test]
+    fn should_parse_bitwise_expression_below_arithmetic() {
+        let source = "1 | 2 + 3 << 1;";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        let root = statements[0].tree.get(statements[0].root_index);
+
+        let Some(StatementNode::Expression(ExpressionNode::Binary(binary))) = root else {
+            panic!("Expected binary root")
+        };
+
+        assert_eq!(binary.operator.kind, TokenKind::Pipe);
+    }
+
+    #[test]
+    fn should_parse_compound_assignment_expression() {
+        let source = "a += b = 2;";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        let tree = &statements[0].tree;
+        let root = tree.get(statements[0].root_index);
+
+        let Some(StatementNode::Expression(ExpressionNode::Assignment(assignment))) = root else {
+            panic!("Expected assignment root")
+        };
+
+        assert_eq!(assignment.operator.kind, TokenKind::PlusEqual);
+        assert!(matches!(
+            tree.get(assignment.value),
+            Some(StatementNode::Expression(ExpressionNode::Assignment(_)))
+        ))
+    }
+
+    #[test]
+    fn should_parse_update_expression() {
+        let source = "++a - b--;";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        let tree = &statements[0].tree;
+        let root = tree.get(statements[0].root_index);
+
+        let Some(StatementNode::Expression(ExpressionNode::Binary(binary))) = root else {
+            panic!("Expected binary root")
+        };
+
+        assert!(matches!(
+            tree.get(binary.left),
+            Some(StatementNode::Expression(ExpressionNode::Update(update))) if update.prefix
+        ));
+        assert!(matches!(
+            tree.get(binary.right),
+            Some(StatementNode::Expression(ExpressionNode::Update(update))) if !update.prefix
+        ))
+    }
+
+    #[test]
+    fn should_reject_invalid_assignment_target() {
+        let source = "1 + a = 2;";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let error = parser::parse(tokens).err().unwrap();
+
+        assert_eq!(error.message, "Invalid assignment target");
+        assert_eq!(error.span.map(|span| span.offset), Some(6));
+    }
+
+    #[test]
+    fn should_parse_call_expression() {
+        let source = "add(1, 2)(3);";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        let tree = &statements[0].tree;
+        let root = tree.get(statements[0].root_index);
+
+        let Some(StatementNode::Expression(ExpressionNode::Call(call))) = root else {
+            panic!("Expected call root")
+        };
+
+        assert_eq!(call.arguments.len(), 1);
+        assert!(matches!(
+            tree.get(call.callee),
+            Some(StatementNode::Expression(ExpressionNode::Call(inner))) if inner.arguments.len() == 2
+        ))
+    }
+
+    #[test]
+    fn should_record_node_spans() {
+        let source = "var a = (1 + b) * f(2);";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        let statement = &statements[0];
+        let span = statement.span();
+
+        assert_eq!(&source[span.offset..span.end], "var a = (1 + b) * f(2)");
+
+        let Some(StatementNode::VariableDefinition(definition)) =
+            statement.tree.get(statement.root_index)
+        else {
+            panic!("Expected variable definition root")
+        };
+
+        let Some(StatementNode::Expression(ExpressionNode::Binary(binary))) =
+            statement.tree.get(definition.expression)
+        else {
+            panic!("Expected binary expression")
+        };
+
+        let left = statement.spans[binary.left];
+        let right = statement.spans[binary.right];
+
+        assert_eq!(&source[left.offset..left.end], "(1 + b)");
+        assert_eq!(&source[right.offset..right.end], "f(2)");
+    }
+
+    #[test]
+    fn should_report_missing_semicolon() {
+        let source = "1 + 2";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let error = parser::parse(tokens).err().unwrap();
+
+        assert_eq!(error.message, "Expected token kind Semicolon");
+        assert_eq!(error.span.map(|span| span.offset), Some(5));
+    }
+
+    #[test]
+    fn should_reject_deeply_nested_input() {
+        let nested = format!("{}1{};", "(".repeat(100), ")".repeat(100));
+        assert!(parser::parse(lexer::tokenize(&nested).unwrap()).is_ok());
+
+        for source in [
+            format!("{}1;", "(".repeat(200_000)),
+            format!("{}1;", "-".repeat(200_000)),
+            format!("{}1;", "{".repeat(200_000)),
+        ] {
+            let tokens = lexer::tokenize(&source).unwrap();
+            let error = parser::parse(tokens).err().unwrap();
+
+            assert_eq!(error.kind, ErrorKind::Syntax);
+            assert_eq!(error.message, "Nesting limit exceeded");
+        }
+    }
+
+    #[test]
+    fn should_parse_interpolation_expression() {
+        let source = "\"a ${1} b ${2 + 3} c\";";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+
+        let root = statements[0].tree.get(statements[0].root_index);
+
+        let Some(StatementNode::Expression(ExpressionNode::Interpolation(parts))) = root else {
+            panic!("Expected interpolation root")
+        };
+
+        assert_eq!(parts.len(), 5);
+    }
+
+    #[test]
+    fn should_parse_optional_property_chain() {
+        let source = "a?.b?.c;";
+
+        let tokens = lexer::tokenize(source).unwrap();
+        let statements = parser::parse(tokens).unwrap();
+        let tree = &statements[0].tree;
+
+        let Some(StatementNode::Expression(ExpressionNode::Get(outer))) =
+            tree.get(statements[0].root_index)
+        else {
+            panic!("Expected property access root")
+        };
+
+        assert_eq!(&source[outer.name.offset..outer.name.end], "c");
+        assert!(matches!(
+            tree.get(outer.object),
+            Some(StatementNode::Expression(ExpressionNode::Get(inner))) if &source[inner.name.offset..inner.name.end] == "b"
+        ));
+    }
+
+    #[test]
+    fn should_parse_imports_exports_and_properties() {
+        let source = "import \"m.lang\" as m; export var a = m.b?.c(1);";
+        let statements = parser::parse(lexer::tokenize(source).unwrap()).unwrap();
+
+        let printed = statements
+            .iter()
+            .map(|statement| statement.debug_print(source))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            printed,
+            vec![
+                "(import \"m.lang\" m)",
+                "(export (a (call (?. (. m b) c) 1)))"
+            ]
+        );
+
+        let error = parser::parse(lexer::tokenize("export print 1;").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Expected token kind Var");
+
+        let error = parser::parse(lexer::tokenize("m.b = 1;").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Invalid assignment target");
+    }
+
+    #[test]
+    fn should_parse_blocks_try_and_throw() {
+        let source = "try { throw \"x\"; { a; } } catch (e) { print e; } finally { b = 1; }";
+        let statements = parser::parse(lexer::tokenize(source).unwrap()).unwrap();
+
+        assert_eq!(
+            statements[0].debug_print(source),
+            "(try (block (throw \"x\") (block a)) (catch e (block (print e))) (finally (block (= b 1))))"
+        );
+
+        let error = parser::parse(lexer::tokenize("try { a; } print 1;").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Expected catch or finally after try block");
+
+        let error = parser::parse(lexer::tokenize("{ a;").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(error.message, "Expected token kind RightBrace");
+    }
 }
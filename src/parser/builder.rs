@@ -1,7 +1,8 @@
-use crate::lexer::token::Token;
+use crate::lexer::token::{Span, Token};
 
 use super::tree::{
-    Binary, ExpressionNode, Literal, Statement, StatementNode, Unary, VariableDefinition,
+    Assignment, Binary, Call, Catch, ExpressionNode, Get, Import, Literal, Statement,
+    StatementNode, Try, Unary, Update, VariableDefinition,
 };
 
 pub struct StatementListBuilder {
@@ -30,20 +31,44 @@ impl StatementListBuilder {
         current_expression.root_index = root_index;
     }
 
-    pub fn add_variable_definition(&mut self, identifier: Token, expression: usize) -> usize {
+    pub fn add_variable_definition(
+        &mut self,
+        keyword: Token,
+        identifier: Token,
+        annotation: Option<Token>,
+        expression: usize,
+    ) -> usize {
         let variable_definition = VariableDefinition {
             identifier,
+            annotation,
             expression,
         };
 
         let statement_node = StatementNode::VariableDefinition(variable_definition);
+        let span = keyword.span().join(self.get_span(expression));
 
-        self.add_node(statement_node)
+        self.add_node(statement_node, span)
     }
 
-    pub fn add_print_statement(&mut self, expression: usize) -> usize {
+    pub fn add_print_statement(&mut self, keyword: Token, expression: usize) -> usize {
         let statement_node = StatementNode::Print(expression);
-        self.add_node(statement_node)
+        let span = keyword.span().join(self.get_span(expression));
+
+        self.add_node(statement_node, span)
+    }
+
+    pub fn add_import_statement(&mut self, keyword: Token, path: Token, alias: Token) -> usize {
+        let statement_node = StatementNode::Import(Import { path, alias });
+        let span = keyword.span().join(alias.span());
+
+        self.add_node(statement_node, span)
+    }
+
+    pub fn add_export_statement(&mut self, keyword: Token, declaration: usize) -> usize {
+        let statement_node = StatementNode::Export(declaration);
+        let span = keyword.span().join(self.get_span(declaration));
+
+        self.add_node(statement_node, span)
     }
 
     pub fn add_binary(&mut self, left: usize, operator: Token, right: usize) -> usize {
@@ -55,8 +80,9 @@ impl StatementListBuilder {
 
         let expression_node = ExpressionNode::Binary(binary);
         let node = StatementNode::Expression(expression_node);
+        let span = self.get_span(left).join(self.get_span(right));
 
-        self.add_node(node)
+        self.add_node(node, span)
     }
 
     pub fn add_unary(&mut self, operator: Token, right: usize) -> usize {
@@ -64,8 +90,9 @@ impl StatementListBuilder {
 
         let expression_node = ExpressionNode::Unary(unary);
         let node = StatementNode::Expression(expression_node);
+        let span = operator.span().join(self.get_span(right));
 
-        self.add_node(node)
+        self.add_node(node, span)
     }
 
     pub fn add_literal(&mut self, token: Token) -> usize {
@@ -74,28 +101,152 @@ impl StatementListBuilder {
         let expression_node = ExpressionNode::Literal(literal);
         let node = StatementNode::Expression(expression_node);
 
-        self.add_node(node)
+        self.add_node(node, token.span())
     }
 
     pub fn add_variable(&mut self, token: Token) -> usize {
         let expression_node = ExpressionNode::Variable(token);
         let node = StatementNode::Expression(expression_node);
 
-        self.add_node(node)
+        self.add_node(node, token.span())
+    }
+
+    pub fn add_assignment(&mut self, target: Token, operator: Token, value: usize) -> usize {
+        let assignment = Assignment {
+            target,
+            operator,
+            value,
+        };
+
+        let expression_node = ExpressionNode::Assignment(assignment);
+        let node = StatementNode::Expression(expression_node);
+        let span = target.span().join(self.get_span(value));
+
+        self.add_node(node, span)
+    }
+
+    pub fn add_update(&mut self, target: Token, operator: Token, prefix: bool) -> usize {
+        let update = Update {
+            target,
+            operator,
+            prefix,
+        };
+
+        let expression_node = ExpressionNode::Update(update);
+        let node = StatementNode::Expression(expression_node);
+
+        self.add_node(node, target.span().join(operator.span()))
+    }
+
+    pub fn add_interpolation(&mut self, parts: Vec<usize>) -> usize {
+        let first = self.get_span(parts[0]);
+        let last = self.get_span(parts[parts.len() - 1]);
+
+        let expression_node = ExpressionNode::Interpolation(parts);
+        let node = StatementNode::Expression(expression_node);
+
+        self.add_node(node, first.join(last))
     }
 
-    pub fn add_grouping(&mut self, index: usize) -> usize {
+    pub fn add_call(
+        &mut self,
+        callee: usize,
+        paren: Token,
+        arguments: Vec<usize>,
+        closing: Token,
+        optional: bool,
+    ) -> usize {
+        let span = self.get_span(callee).join(closing.span());
+
+        let call = Call {
+            callee,
+            paren,
+            arguments,
+            optional,
+        };
+
+        let expression_node = ExpressionNode::Call(call);
+        let node = StatementNode::Expression(expression_node);
+
+        self.add_node(node, span)
+    }
+
+    pub fn add_get(&mut self, object: usize, dot: Token, name: Token, optional: bool) -> usize {
+        let span = self.get_span(object).join(name.span());
+
+        let expression_node = ExpressionNode::Get(Get {
+            object,
+            dot,
+            name,
+            optional,
+        });
+        let node = StatementNode::Expression(expression_node);
+
+        self.add_node(node, span)
+    }
+
+    pub fn get_variable(&self, index: usize) -> Option<Token> {
+        let current_expression = self.statements.last().expect("Expression not started");
+
+        match current_expression.tree.get(index) {
+            Some(StatementNode::Expression(ExpressionNode::Variable(token))) => Some(*token),
+            _ => None,
+        }
+    }
+
+    pub fn add_grouping(&mut self, paren: Token, index: usize, closing: Token) -> usize {
         let grouping_node = ExpressionNode::Grouping(index);
         let node = StatementNode::Expression(grouping_node);
 
-        self.add_node(node)
+        self.add_node(node, paren.span().join(closing.span()))
+    }
+
+    pub fn add_throw_statement(&mut self, keyword: Token, expression: usize) -> usize {
+        let statement_node = StatementNode::Throw(expression);
+        let span = keyword.span().join(self.get_span(expression));
+
+        self.add_node(statement_node, span)
+    }
+
+    pub fn add_block(&mut self, open: Token, statements: Vec<usize>, close: Token) -> usize {
+        let statement_node = StatementNode::Block(statements);
+        let span = open.span().join(close.span());
+
+        self.add_node(statement_node, span)
+    }
+
+    pub fn add_try_statement(
+        &mut self,
+        keyword: Token,
+        body: usize,
+        handler: Option<Catch>,
+        finalizer: Option<usize>,
+    ) -> usize {
+        let last = finalizer
+            .or(handler.as_ref().map(|handler| handler.body))
+            .unwrap_or(body);
+        let span = keyword.span().join(self.get_span(last));
+
+        let statement_node = StatementNode::Try(Try {
+            body,
+            handler,
+            finalizer,
+        });
+
+        self.add_node(statement_node, span)
+    }
+
+    fn get_span(&self, index: usize) -> Span {
+        let current_expression = self.statements.last().expect("Expression not started");
+        current_expression.spans[index]
     }
 
-    fn add_node(&mut self, node: StatementNode) -> usize {
+    fn add_node(&mut self, node: StatementNode, span: Span) -> usize {
         let current_expression = self.statements.last_mut().expect("Expression not started");
 
         let index = current_expression.tree.len();
         current_expression.tree.push(node);
+        current_expression.spans.push(span);
 
         index
     }
@@ -1,38 +1,49 @@
+use std::fmt::Write;
+
+use crate::json::Value;
 use crate::lexer::token::Token;
 
+use super::tree::Assignment;
 use super::tree::Binary;
+use super::tree::Call;
+use super::tree::Constant;
+use super::tree::Get;
+use super::tree::Import;
 use super::tree::Literal;
 use super::tree::Statement;
 use super::tree::StatementVisitor;
+use super::tree::Try;
 use super::tree::Unary;
+use super::tree::Update;
 use super::tree::VariableDefinition;
 
 struct DebugPrinter<'a> {
     tree: &'a Statement,
     source: &'a str,
+    output: String,
 }
 
 impl<'a> DebugPrinter<'a> {
-    pub fn debug_print(&mut self) {
+    pub fn debug_print(mut self) -> String {
         self.tree
             .tree
             .get(self.tree.root_index)
             .unwrap()
-            .visit(self);
+            .visit(&mut self);
 
-        println!()
+        self.output
     }
 
     fn print_node(&mut self, name: &str, expressions: &[usize]) {
-        print!("(");
-        print!("{}", name);
+        self.output.push('(');
+        self.output.push_str(name);
 
         for index in expressions {
-            print!(" ");
+            self.output.push(' ');
             self.tree.tree.get(*index).unwrap().visit(self)
         }
 
-        print!(")");
+        self.output.push(')');
     }
 
     fn get_token_value(&self, token: &Token) -> &'a str {
@@ -42,7 +53,8 @@ impl<'a> DebugPrinter<'a> {
 
 impl StatementVisitor<()> for DebugPrinter<'_> {
     fn handle_literal_expression(&mut self, literal: &Literal) {
-        print!("{}", self.get_token_value(&literal.token));
+        let value = self.get_token_value(&literal.token);
+        self.output.push_str(value);
     }
 
     fn handle_binary_expression(&mut self, binary: &Binary) {
@@ -60,26 +72,392 @@ impl StatementVisitor<()> for DebugPrinter<'_> {
     }
 
     fn handle_variable_expression(&mut self, variable: &Token) {
-        print!("{}", self.get_token_value(variable));
+        let value = self.get_token_value(variable);
+        self.output.push_str(value);
+    }
+
+    fn handle_assignment_expression(&mut self, assignment: &Assignment) {
+        let operator_value = self.get_token_value(&assignment.operator);
+        let target_value = self.get_token_value(&assignment.target);
+
+        let _ = write!(self.output, "({} {} ", operator_value, target_value);
+        self.tree.tree.get(assignment.value).unwrap().visit(self);
+        self.output.push(')');
+    }
+
+    fn handle_update_expression(&mut self, update: &Update) {
+        let operator_value = self.get_token_value(&update.operator);
+        let target_value = self.get_token_value(&update.target);
+
+        if update.prefix {
+            let _ = write!(self.output, "({}{})", operator_value, target_value);
+        } else {
+            let _ = write!(self.output, "({}{})", target_value, operator_value);
+        }
+    }
+
+    fn handle_interpolation_expression(&mut self, parts: &[usize]) {
+        self.print_node("interpolation", parts);
+    }
+
+    fn handle_call_expression(&mut self, call: &Call) {
+        let mut expressions = vec![call.callee];
+        expressions.extend(&call.arguments);
+
+        self.print_node("call", &expressions);
+    }
+
+    fn handle_get_expression(&mut self, get: &Get) {
+        let name = self.get_token_value(&get.name);
+        let dot = self.get_token_value(&get.dot);
+        let _ = write!(self.output, "({} ", dot);
+        self.tree.tree.get(get.object).unwrap().visit(self);
+        let _ = write!(self.output, " {})", name);
+    }
+
+    fn handle_constant_expression(&mut self, constant: &Constant) {
+        let _ = match constant {
+            Constant::Number(value) => write!(self.output, "{}", value),
+            Constant::String(value) => write!(self.output, "{:?}", value),
+            Constant::Boolean(value) => write!(self.output, "{}", value),
+            Constant::Nil => write!(self.output, "nil"),
+        };
     }
 
     fn handle_variable_definition_statement(&mut self, declaration: &VariableDefinition) {
-        let variable_name = self.get_token_value(&declaration.identifier);
-        self.print_node(variable_name, &[declaration.expression]);
+        let mut variable_name = self.get_token_value(&declaration.identifier).to_string();
+        if let Some(annotation) = &declaration.annotation {
+            let _ = write!(variable_name, ": {}", self.get_token_value(annotation));
+        }
+        self.print_node(&variable_name, &[declaration.expression]);
     }
 
     fn handle_print_statement(&mut self, expression: usize) {
         self.print_node("print", &[expression])
     }
+
+    fn handle_import_statement(&mut self, import: &Import) {
+        let path = self.get_token_value(&import.path);
+        let alias = self.get_token_value(&import.alias);
+
+        let _ = write!(self.output, "(import {} {})", path, alias);
+    }
+
+    fn handle_export_statement(&mut self, declaration: usize) {
+        self.print_node("export", &[declaration])
+    }
+
+    fn handle_block_statement(&mut self, statements: &[usize]) {
+        self.print_node("block", statements)
+    }
+
+    fn handle_try_statement(&mut self, statement: &Try) {
+        self.output.push_str("(try ");
+        self.tree.tree.get(statement.body).unwrap().visit(self);
+
+        if let Some(handler) = &statement.handler {
+            let variable = self.get_token_value(&handler.variable);
+            let _ = write!(self.output, " (catch {} ", variable);
+            self.tree.tree.get(handler.body).unwrap().visit(self);
+            self.output.push(')');
+        }
+
+        if let Some(finalizer) = statement.finalizer {
+            self.output.push(' ');
+            self.print_node("finally", &[finalizer]);
+        }
+
+        self.output.push(')');
+    }
+
+    fn handle_throw_statement(&mut self, expression: usize) {
+        self.print_node("throw", &[expression])
+    }
+}
+
+struct JsonPrinter<'a> {
+    tree: &'a Statement,
+    source: &'a str,
+}
+
+impl<'a> JsonPrinter<'a> {
+    fn print_node(&mut self, index: usize) -> Value {
+        let mut value = self.tree.tree.get(index).unwrap().visit(self);
+
+        let span = self.tree.spans[index];
+        if let Value::Object(members) = &mut value {
+            let span = Value::object([
+                ("offset", Value::from(span.offset)),
+                ("end", Value::from(span.end)),
+            ]);
+            members.insert(1, ("span".to_string(), span));
+        }
+
+        value
+    }
+
+    fn print_nodes(&mut self, indices: &[usize]) -> Value {
+        let values = indices
+            .iter()
+            .map(|index| self.print_node(*index))
+            .collect::<Vec<_>>();
+
+        Value::from(values)
+    }
+
+    fn get_token_value(&self, token: &Token) -> Value {
+        Value::from(&self.source[token.offset..token.end])
+    }
+}
+
+impl StatementVisitor<Value> for JsonPrinter<'_> {
+    fn handle_literal_expression(&mut self, literal: &Literal) -> Value {
+        Value::object([
+            ("kind", Value::from("Literal")),
+            ("token", Value::from(format!("{:?}", literal.token.kind))),
+            ("value", self.get_token_value(&literal.token)),
+        ])
+    }
+
+    fn handle_binary_expression(&mut self, binary: &Binary) -> Value {
+        Value::object([
+            ("kind", Value::from("Binary")),
+            ("operator", self.get_token_value(&binary.operator)),
+            ("left", self.print_node(binary.left)),
+            ("right", self.print_node(binary.right)),
+        ])
+    }
+
+    fn handle_grouping_expression(&mut self, index: usize) -> Value {
+        Value::object([
+            ("kind", Value::from("Grouping")),
+            ("expression", self.print_node(index)),
+        ])
+    }
+
+    fn handle_unary_expression(&mut self, unary: &Unary) -> Value {
+        Value::object([
+            ("kind", Value::from("Unary")),
+            ("operator", self.get_token_value(&unary.operator)),
+            ("right", self.print_node(unary.right)),
+        ])
+    }
+
+    fn handle_variable_expression(&mut self, variable: &Token) -> Value {
+        Value::object([
+            ("kind", Value::from("Variable")),
+            ("name", self.get_token_value(variable)),
+        ])
+    }
+
+    fn handle_assignment_expression(&mut self, assignment: &Assignment) -> Value {
+        Value::object([
+            ("kind", Value::from("Assignment")),
+            ("target", self.get_token_value(&assignment.target)),
+            ("operator", self.get_token_value(&assignment.operator)),
+            ("value", self.print_node(assignment.value)),
+        ])
+    }
+
+    fn handle_update_expression(&mut self, update: &Update) -> Value {
+        Value::object([
+            ("kind", Value::from("Update")),
+            ("target", self.get_token_value(&update.target)),
+            ("operator", self.get_token_value(&update.operator)),
+            ("prefix", Value::from(update.prefix)),
+        ])
+    }
+
+    fn handle_interpolation_expression(&mut self, parts: &[usize]) -> Value {
+        Value::object([
+            ("kind", Value::from("Interpolation")),
+            ("parts", self.print_nodes(parts)),
+        ])
+    }
+
+    fn handle_call_expression(&mut self, call: &Call) -> Value {
+        Value::object([
+            ("kind", Value::from("Call")),
+            ("callee", self.print_node(call.callee)),
+            ("optional", Value::Boolean(call.optional)),
+            ("arguments", self.print_nodes(&call.arguments)),
+        ])
+    }
+
+    fn handle_get_expression(&mut self, get: &Get) -> Value {
+        Value::object([
+            ("kind", Value::from("Get")),
+            ("object", self.print_node(get.object)),
+            ("optional", Value::Boolean(get.optional)),
+            ("name", self.get_token_value(&get.name)),
+        ])
+    }
+
+    fn handle_constant_expression(&mut self, constant: &Constant) -> Value {
+        let value = match constant {
+            Constant::Number(value) => Value::Number(*value as f64),
+            Constant::String(value) => Value::from(value.as_str()),
+            Constant::Boolean(value) => Value::from(*value),
+            Constant::Nil => Value::Null,
+        };
+
+        Value::object([("kind", Value::from("Constant")), ("value", value)])
+    }
+
+    fn handle_variable_definition_statement(&mut self, declaration: &VariableDefinition) -> Value {
+        let annotation = match &declaration.annotation {
+            Some(annotation) => self.get_token_value(annotation),
+            None => Value::Null,
+        };
+
+        Value::object([
+            ("kind", Value::from("VariableDefinition")),
+            ("name", self.get_token_value(&declaration.identifier)),
+            ("type", annotation),
+            ("value", self.print_node(declaration.expression)),
+        ])
+    }
+
+    fn handle_print_statement(&mut self, expression: usize) -> Value {
+        Value::object([
+            ("kind", Value::from("Print")),
+            ("expression", self.print_node(expression)),
+        ])
+    }
+
+    fn handle_import_statement(&mut self, import: &Import) -> Value {
+        Value::object([
+            ("kind", Value::from("Import")),
+            ("path", self.get_token_value(&import.path)),
+            ("alias", self.get_token_value(&import.alias)),
+        ])
+    }
+
+    fn handle_export_statement(&mut self, declaration: usize) -> Value {
+        Value::object([
+            ("kind", Value::from("Export")),
+            ("declaration", self.print_node(declaration)),
+        ])
+    }
+
+    fn handle_block_statement(&mut self, statements: &[usize]) -> Value {
+        Value::object([
+            ("kind", Value::from("Block")),
+            ("statements", self.print_nodes(statements)),
+        ])
+    }
+
+    fn handle_try_statement(&mut self, statement: &Try) -> Value {
+        let (variable, handler) = match &statement.handler {
+            Some(handler) => (
+                self.get_token_value(&handler.variable),
+                self.print_node(handler.body),
+            ),
+            None => (Value::Null, Value::Null),
+        };
+        let finalizer = match statement.finalizer {
+            Some(finalizer) => self.print_node(finalizer),
+            None => Value::Null,
+        };
+
+        Value::object([
+            ("kind", Value::from("Try")),
+            ("body", self.print_node(statement.body)),
+            ("variable", variable),
+            ("handler", handler),
+            ("finalizer", finalizer),
+        ])
+    }
+
+    fn handle_throw_statement(&mut self, expression: usize) -> Value {
+        Value::object([
+            ("kind", Value::from("Throw")),
+            ("expression", self.print_node(expression)),
+        ])
+    }
 }
 
 pub trait DebugPrint {
-    fn debug_print(&self, source: &str);
+    /// Renders the statement as an S-expression.
+    fn debug_print(&self, source: &str) -> String;
+
+    /// Renders the statement as a JSON tree, every node carrying its kind and span.
+    fn json_print(&self, source: &str) -> Value;
 }
 
 impl DebugPrint for Statement {
-    fn debug_print(&self, source: &str) {
-        let mut printer = DebugPrinter { tree: self, source };
-        printer.debug_print();
+    fn debug_print(&self, source: &str) -> String {
+        let printer = DebugPrinter {
+            tree: self,
+            source,
+            output: String::new(),
+        };
+        printer.debug_print()
+    }
+
+    fn json_print(&self, source: &str) -> Value {
+        let mut printer = JsonPrinter { tree: self, source };
+        printer.print_node(self.root_index)
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::DebugPrint;
+
+    #[allow(unused_imports)]
+    use crate::lexer::tokenize;
+
+    #[allow(unused_imports)]
+    use crate::parser::parse;
+
+    #[test]
+    fn should_print_s_expressions() {
+        let source = "var a = -1 + 2 * (3);\nprint str(a++, \"${a}\");";
+        let statements = parse(tokenize(source).unwrap()).unwrap();
+
+        let printed = statements
+            .iter()
+            .map(|statement| statement.debug_print(source))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            printed,
+            vec![
+                "(a (+ (- 1) (* 2 (group 3))))",
+                "(print (call str (a++) (interpolation \"${ a }\")))"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_print_json_with_kinds_and_spans() {
+        let source = "print x = 1;";
+        let statements = parse(tokenize(source).unwrap()).unwrap();
+
+        assert_eq!(
+            statements[0].json_print(source).to_string(),
+            concat!(
+                r#"{"kind":"Print","span":{"offset":0,"end":11},"expression":"#,
+                r#"{"kind":"Assignment","span":{"offset":6,"end":11},"target":"x","operator":"=","value":"#,
+                r#"{"kind":"Literal","span":{"offset":10,"end":11},"token":"Number","value":"1"}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn should_mark_the_rest_of_an_optional_chain() {
+        let source = "a.b?.c.d();";
+        let statements = parse(tokenize(source).unwrap()).unwrap();
+        let json = statements[0].json_print(source).to_string();
+
+        let optional = json
+            .match_indices(r#""optional":"#)
+            .map(|(index, key)| json[index + key.len()..].starts_with("true"))
+            .collect::<Vec<_>>();
+
+        // Nodes are nested, so the innermost `a.b` comes first
+        assert_eq!(optional, vec![false, true, true, true]);
     }
 }
@@ -1,5 +1,13 @@
 use crate::lexer::token::TokenKind;
 
+pub fn is_assignment_token(kind: TokenKind) -> bool {
+    kind == TokenKind::Equal || kind.compound_operator().is_some()
+}
+
+pub fn is_coalesce_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::QuestionQuestion)
+}
+
 pub fn is_equality_token(kind: TokenKind) -> bool {
     matches!(kind, TokenKind::BangEqual | TokenKind::EqualEqual)
 }
@@ -11,21 +19,68 @@ pub fn is_comparison_token(kind: TokenKind) -> bool {
     )
 }
 
+pub fn is_bitwise_or_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Pipe)
+}
+
+pub fn is_bitwise_xor_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Caret)
+}
+
+pub fn is_bitwise_and_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::Ampersand)
+}
+
+pub fn is_shift_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::LessLess | TokenKind::GreaterGreater)
+}
+
 pub fn is_term_token(kind: TokenKind) -> bool {
     matches!(kind, TokenKind::Minus | TokenKind::Plus)
 }
 
 pub fn is_factor_token(kind: TokenKind) -> bool {
-    matches!(kind, TokenKind::Slash | TokenKind::Star)
+    matches!(
+        kind,
+        TokenKind::Slash | TokenKind::Star | TokenKind::Percent | TokenKind::SlashSlash
+    )
 }
 
 pub fn is_unary_token(kind: TokenKind) -> bool {
-    matches!(kind, TokenKind::Bang | TokenKind::Minus)
+    matches!(kind, TokenKind::Bang | TokenKind::Minus | TokenKind::Tilde)
+}
+
+pub fn is_power_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::StarStar)
+}
+
+pub fn is_call_token(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::LeftParen | TokenKind::Dot | TokenKind::QuestionDot
+    )
 }
 
 pub fn is_primary_token(kind: TokenKind) -> bool {
     matches!(
         kind,
-        TokenKind::True | TokenKind::False | TokenKind::String | TokenKind::Number
+        TokenKind::True | TokenKind::False | TokenKind::Nil | TokenKind::String | TokenKind::Number
+    )
+}
+
+pub fn is_update_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::PlusPlus | TokenKind::MinusMinus)
+}
+
+pub fn is_interpolation_continue_token(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::InterpolationMiddle)
+}
+
+pub fn is_interpolation_segment_token(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::InterpolationStart
+            | TokenKind::InterpolationMiddle
+            | TokenKind::InterpolationEnd
     )
 }
@@ -1,9 +1,43 @@
-use crate::lexer::token::Token;
+use crate::lexer::token::{Span, Token, TokenKind};
+
+use super::rules::is_interpolation_segment_token;
 
 pub struct Literal {
     pub token: Token,
 }
 
+impl Literal {
+    pub fn constant(&self, source: &str) -> Constant {
+        let token = &self.token;
+
+        match token.kind {
+            TokenKind::String => {
+                Constant::String(source[token.offset + 1..token.end - 1].to_string())
+            }
+            TokenKind::Number => Constant::Number(source[token.offset..token.end].parse().unwrap()),
+            TokenKind::InterpolationEnd => {
+                Constant::String(source[token.offset + 1..token.end - 1].to_string())
+            }
+            kind if is_interpolation_segment_token(kind) => {
+                Constant::String(source[token.offset + 1..token.end - 2].to_string())
+            }
+            TokenKind::True => Constant::Boolean(true),
+            TokenKind::False => Constant::Boolean(false),
+            TokenKind::Nil => Constant::Nil,
+            kind => panic!("Unexpected literal type {:?}", kind),
+        }
+    }
+}
+
+/// A value computed ahead of time by the optimizer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Number(f32),
+    String(String),
+    Boolean(bool),
+    Nil,
+}
+
 pub struct Binary {
     pub left: usize,
     pub operator: Token,
@@ -15,8 +49,55 @@ pub struct Unary {
     pub right: usize,
 }
 
+pub struct Assignment {
+    pub target: Token,
+    pub operator: Token,
+    pub value: usize,
+}
+
+pub struct Update {
+    pub target: Token,
+    pub operator: Token,
+    pub prefix: bool,
+}
+
+pub struct Call {
+    pub callee: usize,
+    pub paren: Token,
+    pub arguments: Vec<usize>,
+    /// Set when the call follows a `?.` earlier in the same chain.
+    pub optional: bool,
+}
+
+pub struct Get {
+    pub object: usize,
+    /// Either `.` or `?.`.
+    pub dot: Token,
+    pub name: Token,
+    /// Set from the first `?.` of a chain on, so that a nil receiver makes
+    /// the rest of the chain nil instead of failing.
+    pub optional: bool,
+}
+
+pub struct Import {
+    pub path: Token,
+    pub alias: Token,
+}
+
+pub struct Catch {
+    pub variable: Token,
+    pub body: usize,
+}
+
+pub struct Try {
+    pub body: usize,
+    pub handler: Option<Catch>,
+    pub finalizer: Option<usize>,
+}
+
 pub struct VariableDefinition {
     pub identifier: Token,
+    pub annotation: Option<Token>,
     pub expression: usize,
 }
 
@@ -26,12 +107,23 @@ pub enum ExpressionNode {
     Literal(Literal),
     Grouping(usize),
     Variable(Token),
+    Assignment(Assignment),
+    Update(Update),
+    Interpolation(Vec<usize>),
+    Call(Call),
+    Get(Get),
+    Constant(Constant),
 }
 
 pub enum StatementNode {
     Expression(ExpressionNode),
     VariableDefinition(VariableDefinition),
     Print(usize),
+    Import(Import),
+    Export(usize),
+    Block(Vec<usize>),
+    Try(Try),
+    Throw(usize),
 }
 
 impl StatementNode {
@@ -43,17 +135,31 @@ impl StatementNode {
                 ExpressionNode::Grouping(expr) => visitor.handle_grouping_expression(*expr),
                 ExpressionNode::Binary(expr) => visitor.handle_binary_expression(expr),
                 ExpressionNode::Variable(token) => visitor.handle_variable_expression(token),
+                ExpressionNode::Assignment(expr) => visitor.handle_assignment_expression(expr),
+                ExpressionNode::Update(expr) => visitor.handle_update_expression(expr),
+                ExpressionNode::Interpolation(parts) => {
+                    visitor.handle_interpolation_expression(parts)
+                }
+                ExpressionNode::Call(expr) => visitor.handle_call_expression(expr),
+                ExpressionNode::Get(expr) => visitor.handle_get_expression(expr),
+                ExpressionNode::Constant(value) => visitor.handle_constant_expression(value),
             },
             StatementNode::VariableDefinition(definition) => {
                 visitor.handle_variable_definition_statement(definition)
             }
             StatementNode::Print(expr) => visitor.handle_print_statement(*expr),
+            StatementNode::Import(import) => visitor.handle_import_statement(import),
+            StatementNode::Export(declaration) => visitor.handle_export_statement(*declaration),
+            StatementNode::Block(statements) => visitor.handle_block_statement(statements),
+            StatementNode::Try(statement) => visitor.handle_try_statement(statement),
+            StatementNode::Throw(expression) => visitor.handle_throw_statement(*expression),
         }
     }
 }
 
 pub struct Statement {
     pub tree: Vec<StatementNode>,
+    pub spans: Vec<Span>,
     pub root_index: usize,
 }
 
@@ -67,9 +173,14 @@ impl Statement {
     pub fn new() -> Self {
         Self {
             tree: Vec::new(),
+            spans: Vec::new(),
             root_index: 0,
         }
     }
+
+    pub fn span(&self) -> Span {
+        self.spans[self.root_index]
+    }
 }
 
 pub trait StatementVisitor<T> {
@@ -83,7 +194,29 @@ pub trait StatementVisitor<T> {
 
     fn handle_variable_expression(&mut self, variable: &Token) -> T;
 
+    fn handle_assignment_expression(&mut self, assignment: &Assignment) -> T;
+
+    fn handle_update_expression(&mut self, update: &Update) -> T;
+
+    fn handle_interpolation_expression(&mut self, parts: &[usize]) -> T;
+
+    fn handle_call_expression(&mut self, call: &Call) -> T;
+
+    fn handle_get_expression(&mut self, get: &Get) -> T;
+
+    fn handle_constant_expression(&mut self, constant: &Constant) -> T;
+
     fn handle_variable_definition_statement(&mut self, declaration: &VariableDefinition) -> T;
 
     fn handle_print_statement(&mut self, expression: usize) -> T;
+
+    fn handle_import_statement(&mut self, import: &Import) -> T;
+
+    fn handle_export_statement(&mut self, declaration: usize) -> T;
+
+    fn handle_block_statement(&mut self, statements: &[usize]) -> T;
+
+    fn handle_try_statement(&mut self, statement: &Try) -> T;
+
+    fn handle_throw_statement(&mut self, expression: usize) -> T;
 }
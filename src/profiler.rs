@@ -0,0 +1,350 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::interpreter::hook::{Hook, Location};
+use crate::interpreter::ProgramContext;
+use crate::source::{LineIndex, SourceId};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Entry {
+    pub count: usize,
+    pub inclusive: Duration,
+    pub exclusive: Duration,
+}
+
+impl Entry {
+    fn record(&mut self, inclusive: Duration, exclusive: Duration) {
+        self.count += 1;
+        self.inclusive += inclusive;
+        self.exclusive += exclusive;
+    }
+}
+
+struct Frame {
+    name: String,
+    depth: usize,
+    start: Instant,
+    children: Duration,
+}
+
+#[derive(Default)]
+struct Profile {
+    statements: HashMap<String, Entry>,
+    functions: HashMap<String, Entry>,
+    stacks: BTreeMap<String, Duration>,
+    frames: Vec<Frame>,
+    lines: Option<(SourceId, LineIndex)>,
+}
+
+impl Profile {
+    fn statement_name(&mut self, location: &Location) -> String {
+        // Sources are only re-indexed when execution moves to a different one
+        if self
+            .lines
+            .as_ref()
+            .is_none_or(|(id, _)| *id != location.span.source)
+        {
+            self.lines = Some((location.span.source, LineIndex::new(location.source)));
+        }
+
+        let (_, lines) = self.lines.as_ref().unwrap();
+        let span = location.span;
+        let text = &location.source[span.offset..span.end];
+
+        // Statements sharing a line are told apart by their column, and frame
+        // names are joined with semicolons in folded stacks
+        format!(
+            "{}:{}:{}: {}",
+            location.name,
+            lines.line(span.offset),
+            lines.column(span.offset),
+            text.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .trim_end_matches(';')
+                .replace(';', ",")
+        )
+    }
+
+    fn enter(&mut self, location: &Location) {
+        let name = match location.function {
+            Some(function) => function.to_string(),
+            None if location.statement => self.statement_name(location),
+            None => return,
+        };
+
+        self.frames.push(Frame {
+            name,
+            depth: location.depth,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn exit(&mut self, location: &Location) {
+        if self
+            .frames
+            .last()
+            .is_none_or(|frame| frame.depth != location.depth)
+        {
+            return;
+        }
+
+        let stack = self
+            .frames
+            .iter()
+            .map(|frame| frame.name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let frame = self.frames.pop().unwrap();
+        let inclusive = frame.start.elapsed();
+        let exclusive = inclusive.saturating_sub(frame.children);
+
+        if let Some(parent) = self.frames.last_mut() {
+            parent.children += inclusive;
+        }
+
+        let entries = match location.function {
+            Some(_) => &mut self.functions,
+            None => &mut self.statements,
+        };
+
+        entries
+            .entry(frame.name)
+            .or_default()
+            .record(inclusive, exclusive);
+        *self.stacks.entry(stack).or_default() += exclusive;
+    }
+}
+
+/// Records call counts and inclusive and exclusive time for every statement,
+/// including those of imported modules, and every function call.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    profile: Rc<RefCell<Profile>>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn statements(&self) -> Vec<(String, Entry)> {
+        Self::sorted(&self.profile.borrow().statements)
+    }
+
+    pub fn functions(&self) -> Vec<(String, Entry)> {
+        Self::sorted(&self.profile.borrow().functions)
+    }
+
+    /// Renders both tables sorted by inclusive time, slowest first.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+
+        for (title, entries) in [
+            ("Statements", self.statements()),
+            ("Functions", self.functions()),
+        ] {
+            let _ = writeln!(
+                report,
+                "{:>8} {:>12} {:>12}  {}",
+                "count", "inclusive", "exclusive", title
+            );
+
+            for (name, entry) in entries {
+                let _ = writeln!(
+                    report,
+                    "{:>8} {:>12} {:>12}  {}",
+                    entry.count,
+                    Self::format_duration(entry.inclusive),
+                    Self::format_duration(entry.exclusive),
+                    name
+                );
+            }
+        }
+
+        report
+    }
+
+    /// Renders one `frame;frame;frame microseconds` line per distinct stack,
+    /// the input format of flame-graph tools.
+    pub fn folded(&self) -> String {
+        self.profile
+            .borrow()
+            .stacks
+            .iter()
+            .map(|(stack, time)| format!("{} {}\n", stack, time.as_micros()))
+            .collect()
+    }
+
+    fn sorted(entries: &HashMap<String, Entry>) -> Vec<(String, Entry)> {
+        let mut entries = entries
+            .iter()
+            .map(|(name, entry)| (name.clone(), *entry))
+            .collect::<Vec<_>>();
+        entries.sort_by(|(left_name, left), (right_name, right)| {
+            right
+                .inclusive
+                .cmp(&left.inclusive)
+                .then_with(|| left_name.cmp(right_name))
+        });
+
+        entries
+    }
+
+    fn format_duration(duration: Duration) -> String {
+        format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+    }
+}
+
+impl Hook for Profiler {
+    fn before_evaluate(&mut self, _context: &mut ProgramContext, location: Location) -> Result<()> {
+        self.profile.borrow_mut().enter(&location);
+        Ok(())
+    }
+
+    fn after_evaluate(&mut self, _context: &mut ProgramContext, location: Location) -> Result<()> {
+        self.profile.borrow_mut().exit(&location);
+        Ok(())
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::Profiler;
+
+    #[allow(unused_imports)]
+    use crate::interpreter::io::Streams;
+
+    #[allow(unused_imports)]
+    use crate::interpreter::ProgramContext;
+
+    #[allow(dead_code)]
+    fn profile(source: &str) -> Profiler {
+        let streams = Streams::new(std::io::sink(), std::io::sink(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+
+        let profiler = Profiler::new();
+        program.set_hook(Some(Box::new(profiler.clone())));
+        program.interpret(source).unwrap();
+
+        profiler
+    }
+
+    #[test]
+    fn should_count_statements_and_function_calls() {
+        let profiler = profile("var a = 1;\nprint str(a);\nprint str(a) + str(2);");
+
+        let mut statements = profiler
+            .statements()
+            .into_iter()
+            .map(|(name, entry)| (name, entry.count))
+            .collect::<Vec<_>>();
+        statements.sort();
+
+        assert_eq!(
+            statements,
+            vec![
+                ("<input>:1:1: var a = 1".to_string(), 1),
+                ("<input>:2:1: print str(a)".to_string(), 1),
+                ("<input>:3:1: print str(a) + str(2)".to_string(), 1),
+            ]
+        );
+
+        let functions = profiler.functions();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].0, "str");
+        assert_eq!(functions[0].1.count, 3);
+        assert!(functions[0].1.exclusive <= functions[0].1.inclusive);
+    }
+
+    #[test]
+    fn should_keep_statements_on_one_line_apart() {
+        let profiler = profile("var a = 1; print a; print a;");
+
+        let mut statements = profiler
+            .statements()
+            .into_iter()
+            .map(|(name, entry)| (name, entry.count))
+            .collect::<Vec<_>>();
+        statements.sort();
+
+        assert_eq!(
+            statements,
+            vec![
+                ("<input>:1:12: print a".to_string(), 1),
+                ("<input>:1:1: var a = 1".to_string(), 1),
+                ("<input>:1:21: print a".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_emit_folded_stacks() {
+        let profiler = profile("print str(1);\nvar a = 2;");
+
+        let stacks = profiler
+            .folded()
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            stacks,
+            vec![
+                "<input>:1:1: print str(1)",
+                "<input>:1:1: print str(1);str",
+                "<input>:2:1: var a = 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn should_attribute_statements_of_imported_modules() {
+        let directory = std::env::temp_dir().join(format!("lang-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("util.lang"), "{\n    print str(1);\n}").unwrap();
+
+        let main = directory.join("main.lang");
+        let source = "import \"util.lang\" as util;";
+        std::fs::write(&main, source).unwrap();
+
+        let streams = Streams::new(std::io::sink(), std::io::sink(), std::io::empty());
+        let mut program = ProgramContext::with_streams(streams);
+        let profiler = Profiler::new();
+        program.set_hook(Some(Box::new(profiler.clone())));
+        program.interpret_file(&main, source).unwrap();
+
+        let util = directory.join("util.lang").canonicalize().unwrap();
+        let stacks = profiler
+            .folded()
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+            .collect::<Vec<_>>();
+
+        let import = format!("{}:1:1: import \"util.lang\" as util", main.display());
+        let block = format!("{};{}:1:1: {{ print str(1), }}", import, util.display());
+        let print = format!("{};{}:2:5: print str(1)", block, util.display());
+        assert_eq!(
+            stacks,
+            vec![import, block, print.clone(), format!("{};str", print)]
+        );
+    }
+
+    #[test]
+    fn should_list_every_entry_in_the_report() {
+        let profiler = profile("print str(1);");
+        let report = profiler.report();
+
+        assert!(report.contains("  Statements\n"));
+        assert!(report.contains("  <input>:1:1: print str(1)\n"));
+        assert!(report.contains("  Functions\n"));
+        assert!(report.contains("  str\n"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::SourceLocation;
+use crate::lexer::token::Span;
+
+/// Identifies a source registered with a `SourceRegistry`. Sources that were
+/// never registered, such as those given to the formatter or the linter, share
+/// the default id.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct SourceId(u32);
+
+impl SourceId {
+    pub const UNKNOWN: SourceId = SourceId(0);
+}
+
+pub struct SourceFile {
+    /// The path of a file, or a name such as `<repl:2>` for other input.
+    pub name: String,
+    pub text: Rc<str>,
+    pub lines: LineIndex,
+}
+
+/// Owns the sources of a session, so spans stay meaningful after the input
+/// they came from is gone and diagnostics can name their origin. Ids are never
+/// reused, a span into a removed source is simply no longer located.
+#[derive(Default)]
+pub struct SourceRegistry {
+    files: HashMap<SourceId, SourceFile>,
+    last_id: u32,
+}
+
+impl SourceRegistry {
+    pub fn add(&mut self, name: impl Into<String>, text: &str) -> SourceId {
+        self.last_id += 1;
+        let id = SourceId(self.last_id);
+
+        let file = SourceFile {
+            name: name.into(),
+            text: Rc::from(text),
+            lines: LineIndex::new(text),
+        };
+        self.files.insert(id, file);
+
+        id
+    }
+
+    pub fn get(&self, id: SourceId) -> Option<&SourceFile> {
+        self.files.get(&id)
+    }
+
+    pub fn remove(&mut self, id: SourceId) -> Option<SourceFile> {
+        self.files.remove(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Describes where a span starts, or `None` if its source is unknown.
+    pub fn location(&self, span: Span) -> Option<SourceLocation> {
+        let file = self.get(span.source)?;
+        let line = file.lines.line(span.offset);
+
+        Some(SourceLocation {
+            path: file.name.clone(),
+            line,
+            column: file.lines.column(span.offset),
+            text: file
+                .lines
+                .line_text(&file.text, line)
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+        })
+    }
+}
+
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    /// The end offset of every multi-byte character with the number of extra
+    /// bytes up to and including it, so columns can count characters.
+    wide_characters: Vec<(usize, usize)>,
+    length: usize,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_characters = Vec::new();
+        let mut extra = 0;
+        for (offset, character) in source.char_indices() {
+            if character == '\n' {
+                line_starts.push(offset + 1);
+            } else if character.len_utf8() > 1 {
+                extra += character.len_utf8() - 1;
+                wide_characters.push((offset + character.len_utf8(), extra));
+            }
+        }
+
+        Self {
+            line_starts,
+            wide_characters,
+            length: source.len(),
+        }
+    }
+
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset)
+    }
+
+    /// The column of a byte offset, counted in characters from 1.
+    pub fn column(&self, offset: usize) -> usize {
+        let start = self.line_starts[self.line(offset) - 1];
+        offset - start - (self.extra_bytes(offset) - self.extra_bytes(start)) + 1
+    }
+
+    fn extra_bytes(&self, offset: usize) -> usize {
+        match self
+            .wide_characters
+            .partition_point(|(end, _)| *end <= offset)
+        {
+            0 => 0,
+            index => self.wide_characters[index - 1].1,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    pub fn line_range(&self, line: usize) -> Option<(usize, usize)> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.length, |next| next - 1);
+
+        Some((start, end))
+    }
+
+    pub fn line_text<'a>(&self, source: &'a str, line: usize) -> Option<&'a str> {
+        let (start, end) = self.line_range(line)?;
+        Some(source[start..end].trim_end_matches('\r'))
+    }
+}
+
+mod tests {
+
+    #[allow(unused_imports)]
+    use super::{LineIndex, SourceId, SourceRegistry};
+
+    #[allow(unused_imports)]
+    use crate::lexer::token::Span;
+
+    #[test]
+    fn should_map_offsets_to_lines_and_columns() {
+        let source = "var a = 1;\nprint a;\n\nprint 2;";
+        let lines = LineIndex::new(source);
+
+        assert_eq!(lines.line_count(), 4);
+
+        assert_eq!(lines.line(0), 1);
+        assert_eq!(lines.line(10), 1);
+        assert_eq!(lines.line(11), 2);
+        assert_eq!(lines.column(17), 7);
+        assert_eq!(lines.line(20), 3);
+        assert_eq!(lines.line(21), 4);
+
+        assert_eq!(lines.line_text(source, 2), Some("print a;"));
+        assert_eq!(lines.line_text(source, 3), Some(""));
+        assert_eq!(lines.line_text(source, 4), Some("print 2;"));
+        assert_eq!(lines.line_text(source, 5), None);
+
+        let source = "é\nvar ü = \"ö\" - 1;";
+        let lines = LineIndex::new(source);
+        assert_eq!(lines.column(source.find('=').unwrap()), 7);
+        assert_eq!(lines.column(source.find('-').unwrap()), 13);
+    }
+
+    #[test]
+    fn should_locate_spans_in_registered_sources() {
+        let mut sources = SourceRegistry::default();
+        let first = sources.add("<repl:1>", "var a = 1;");
+        let second = sources.add("main.lang", "print 1;\n  print a;");
+
+        assert_ne!(first, second);
+        assert_eq!(sources.get(first).unwrap().name, "<repl:1>");
+
+        let span = Span {
+            offset: 11,
+            end: 16,
+            source: second,
+        };
+        let location = sources.location(span).unwrap();
+        assert_eq!(location.path, "main.lang");
+        assert_eq!((location.line, location.column), (2, 3));
+        assert_eq!(location.text, "print a;");
+
+        let unregistered = Span {
+            source: SourceId::UNKNOWN,
+            ..span
+        };
+        assert_eq!(sources.location(unregistered), None);
+
+        assert!(sources.remove(second).is_some());
+        assert_eq!(sources.location(span), None);
+        assert_ne!(sources.add("<repl:2>", ""), second);
+        assert_eq!(sources.len(), 2);
+    }
+}